@@ -0,0 +1,79 @@
+use rg3d::{
+    core::{algebra::Vector3, pool::Handle},
+    scene::{node::Node, RigidBodyHandle, Scene},
+};
+
+// Barrels are instances of assets/models/barrel.FBX, root of every instance is named after
+// the model and bound to a rigid body.
+const BARREL_PREFIX: &str = "barrel";
+
+pub const MAX_HEALTH: f32 = 40.0;
+
+// Barrel caught in an explosion does not go off at once, so a chain reaction is seen as a
+// sequence of blasts rather than a single one.
+pub const CHAIN_DELAY: f32 = 0.15;
+
+struct Barrel {
+    node: Handle<Node>,
+    body: RigidBodyHandle,
+    health: f32,
+    // Time left until the explosion, set when health drops to zero.
+    fuse: Option<f32>,
+}
+
+/// Every explosive barrel of a level.
+#[derive(Default)]
+pub struct Barrels {
+    barrels: Vec<Barrel>,
+}
+
+impl Barrels {
+    pub fn find(scene: &Scene) -> Self {
+        let barrels = scene
+            .graph
+            .pair_iter()
+            .filter(|(_, node)| node.name().to_lowercase().starts_with(BARREL_PREFIX))
+            .filter_map(|(node, _)| {
+                scene.physics_binder.body_of(node).map(|body| Barrel {
+                    node,
+                    body,
+                    health: MAX_HEALTH,
+                    fuse: None,
+                })
+            })
+            .collect();
+        Self { barrels }
+    }
+
+    /// Damages the barrel bound to the body, if there is one. Barrel which runs out of health
+    /// explodes after `delay` seconds.
+    pub fn damage(&mut self, body: RigidBodyHandle, amount: f32, delay: f32) {
+        if let Some(barrel) = self.barrels.iter_mut().find(|barrel| barrel.body == body) {
+            barrel.health = (barrel.health - amount).max(0.0);
+            if barrel.health <= 0.0 && barrel.fuse.is_none() {
+                barrel.fuse = Some(delay);
+            }
+        }
+    }
+
+    /// Removes every barrel whose fuse has burnt out from the scene and returns positions of
+    /// the explosions.
+    pub fn update(&mut self, dt: f32, scene: &mut Scene) -> Vec<Vector3<f32>> {
+        let mut explosions = Vec::new();
+        self.barrels
+            .retain_mut(|barrel| match barrel.fuse.as_mut() {
+                Some(fuse) if *fuse <= 0.0 => {
+                    explosions.push(scene.graph[barrel.node].global_position());
+                    // This also removes the body, so it does not block the blast.
+                    scene.remove_node(barrel.node);
+                    false
+                }
+                Some(fuse) => {
+                    *fuse -= dt;
+                    true
+                }
+                None => true,
+            });
+        explosions
+    }
+}
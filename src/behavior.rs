@@ -0,0 +1,376 @@
+use std::{fmt, path::Path};
+
+/// Result of a single tick of a node.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Status {
+    Success,
+    Failure,
+    Running,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Condition {
+    SeesEnemy,
+    RemembersEnemy,
+    LowHealth,
+    HasAmmo,
+    NeedsReload,
+    Aggressive,
+}
+
+// Names of conditions in behavior files.
+const CONDITIONS: &[(&str, Condition)] = &[
+    ("sees_enemy", Condition::SeesEnemy),
+    ("remembers_enemy", Condition::RemembersEnemy),
+    ("low_health", Condition::LowHealth),
+    ("has_ammo", Condition::HasAmmo),
+    ("needs_reload", Condition::NeedsReload),
+    ("aggressive", Condition::Aggressive),
+];
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Action {
+    // Stop walking.
+    Hold,
+    Shoot,
+    Reload,
+    // Go to the last known position of an enemy.
+    Investigate,
+    // Walk to random places.
+    Patrol,
+    // Run away from the last known position of an enemy.
+    Retreat,
+    // Go to the best cover from the last known position of an enemy.
+    TakeCover,
+}
+
+// Names of actions in behavior files.
+const ACTIONS: &[(&str, Action)] = &[
+    ("hold", Action::Hold),
+    ("shoot", Action::Shoot),
+    ("reload", Action::Reload),
+    ("investigate", Action::Investigate),
+    ("patrol", Action::Patrol),
+    ("retreat", Action::Retreat),
+    ("take_cover", Action::TakeCover),
+];
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, _) = CONDITIONS.iter().find(|(_, c)| c == self).unwrap();
+        write!(f, "condition {}", name)
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, _) = ACTIONS.iter().find(|(_, a)| a == self).unwrap();
+        write!(f, "action {}", name)
+    }
+}
+
+/// Whatever is controlled by a behavior tree. Tree itself knows nothing about bots, so it
+/// can be ticked with any agent.
+pub trait Agent {
+    fn check(&self, condition: Condition) -> bool;
+
+    fn act(&mut self, action: Action) -> Status;
+}
+
+enum Node {
+    // Runs children in order until one of them does not fail.
+    Selector {
+        label: Option<String>,
+        children: Vec<Node>,
+    },
+    // Runs children in order until one of them does not succeed.
+    Sequence {
+        label: Option<String>,
+        children: Vec<Node>,
+    },
+    // Swaps success and failure of its only child.
+    Invert(Box<Node>),
+    Condition(Condition),
+    Action(Action),
+}
+
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Node::Selector { label, .. } | Node::Sequence { label, .. } => {
+                let kind = if let Node::Selector { .. } = self {
+                    "selector"
+                } else {
+                    "sequence"
+                };
+                match label {
+                    Some(label) => write!(f, "{} {}", kind, label),
+                    None => write!(f, "{}", kind),
+                }
+            }
+            Node::Invert(_) => write!(f, "invert"),
+            Node::Condition(condition) => write!(f, "{}", condition),
+            Node::Action(action) => write!(f, "{}", action),
+        }
+    }
+}
+
+impl Node {
+    fn tick<A: Agent>(&self, agent: &mut A, trace: &mut Option<&mut Vec<String>>) -> Status {
+        let status = match self {
+            Node::Selector { children, .. } => children
+                .iter()
+                .map(|child| child.tick(agent, trace))
+                .find(|status| *status != Status::Failure)
+                .unwrap_or(Status::Failure),
+            Node::Sequence { children, .. } => children
+                .iter()
+                .map(|child| child.tick(agent, trace))
+                .find(|status| *status != Status::Success)
+                .unwrap_or(Status::Success),
+            Node::Invert(child) => match child.tick(agent, trace) {
+                Status::Success => Status::Failure,
+                Status::Failure => Status::Success,
+                Status::Running => Status::Running,
+            },
+            Node::Condition(condition) => {
+                if agent.check(*condition) {
+                    Status::Success
+                } else {
+                    Status::Failure
+                }
+            }
+            Node::Action(action) => agent.act(*action),
+        };
+        if let Some(trace) = trace.as_mut() {
+            trace.push(format!("{} -> {:?}", self, status));
+        }
+        status
+    }
+}
+
+/// Decision making of a bot described in a text file. Every line is a node, children are
+/// indented deeper than their parent:
+///
+/// ```text
+/// selector root
+///     sequence engage
+///         condition sees_enemy
+///         action shoot
+///     action patrol
+/// ```
+///
+/// Composite nodes (`selector`, `sequence`) may have a label which is shown in traces. The
+/// whole tree is evaluated from the root on every tick, so there is no state to reset.
+pub struct BehaviorTree {
+    root: Node,
+}
+
+impl Default for BehaviorTree {
+    // Used when behavior file cannot be loaded, bot just wanders around.
+    fn default() -> Self {
+        Self {
+            root: Node::Action(Action::Patrol),
+        }
+    }
+}
+
+// Single meaningful line of a behavior file.
+struct Line<'a> {
+    number: usize,
+    indent: usize,
+    tokens: Vec<&'a str>,
+}
+
+impl BehaviorTree {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("unable to read {}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let lines = text
+            .lines()
+            .enumerate()
+            .filter_map(|(i, line)| {
+                let trimmed = line.trim();
+                if trimmed.is_empty() || trimmed.starts_with("//") || trimmed.starts_with('#') {
+                    None
+                } else {
+                    Some(Line {
+                        number: i + 1,
+                        indent: line.len() - line.trim_start().len(),
+                        tokens: trimmed.split_whitespace().collect(),
+                    })
+                }
+            })
+            .collect::<Vec<_>>();
+        let mut position = 0;
+        let root = parse_node(&lines, &mut position)?;
+        match lines.get(position) {
+            Some(line) => Err(format!(
+                "line {}: tree must have exactly one root",
+                line.number
+            )),
+            None => Ok(Self { root }),
+        }
+    }
+
+    /// Runs the tree once. When `trace` is given, every node which ran is written to it
+    /// together with its result, children first.
+    pub fn tick<A: Agent>(&self, agent: &mut A, mut trace: Option<&mut Vec<String>>) -> Status {
+        self.root.tick(agent, &mut trace)
+    }
+}
+
+fn parse_node(lines: &[Line], position: &mut usize) -> Result<Node, String> {
+    let line = lines
+        .get(*position)
+        .ok_or_else(|| "behavior tree is empty".to_owned())?;
+    *position += 1;
+
+    // Children are every following line indented deeper than this one.
+    let mut children = Vec::new();
+    let mut children_indent = None;
+    while let Some(child) = lines.get(*position).filter(|l| l.indent > line.indent) {
+        if *children_indent.get_or_insert(child.indent) != child.indent {
+            return Err(format!("line {}: inconsistent indentation", child.number));
+        }
+        children.push(parse_node(lines, position)?);
+    }
+
+    let error = |text: &str| Err(format!("line {}: {}", line.number, text));
+    match line.tokens.as_slice() {
+        ["selector", label @ ..] | ["sequence", label @ ..] if label.len() <= 1 => {
+            if children.is_empty() {
+                return error("composite node must have children");
+            }
+            let label = label.first().map(|l| l.to_string());
+            if line.tokens[0] == "selector" {
+                Ok(Node::Selector { label, children })
+            } else {
+                Ok(Node::Sequence { label, children })
+            }
+        }
+        ["invert"] => {
+            if children.len() != 1 {
+                return error("invert must have exactly one child");
+            }
+            Ok(Node::Invert(Box::new(children.pop().unwrap())))
+        }
+        ["condition", name] | ["action", name] if !children.is_empty() => {
+            error(&format!("'{}' cannot have children", name))
+        }
+        ["condition", name] => match CONDITIONS.iter().find(|(n, _)| n == name) {
+            Some((_, condition)) => Ok(Node::Condition(*condition)),
+            None => error(&format!("unknown condition '{}'", name)),
+        },
+        ["action", name] => match ACTIONS.iter().find(|(n, _)| n == name) {
+            Some((_, action)) => Ok(Node::Action(*action)),
+            None => error(&format!("unknown action '{}'", name)),
+        },
+        _ => error(&format!("invalid node '{}'", line.tokens.join(" "))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Agent which sees an enemy and succeeds in every action.
+    struct TestAgent {
+        actions: Vec<Action>,
+    }
+
+    impl Agent for TestAgent {
+        fn check(&self, condition: Condition) -> bool {
+            condition == Condition::SeesEnemy
+        }
+
+        fn act(&mut self, action: Action) -> Status {
+            self.actions.push(action);
+            Status::Success
+        }
+    }
+
+    fn parse_error(text: &str) -> Option<String> {
+        BehaviorTree::parse(text).err()
+    }
+
+    #[test]
+    fn nested_nodes_are_ticked_in_order() {
+        let tree = BehaviorTree::parse(
+            "selector root\n\
+             \x20   sequence flee\n\
+             \x20       condition low_health\n\
+             \x20       action retreat\n\
+             \x20   sequence engage\n\
+             \x20       invert\n\
+             \x20           condition needs_reload\n\
+             \x20       action shoot\n\
+             \x20   action patrol\n",
+        )
+        .unwrap();
+        let mut agent = TestAgent {
+            actions: Vec::new(),
+        };
+        let mut trace = Vec::new();
+        assert_eq!(tree.tick(&mut agent, Some(&mut trace)), Status::Success);
+        assert_eq!(agent.actions, vec![Action::Shoot]);
+        assert_eq!(
+            trace,
+            vec![
+                "condition low_health -> Failure",
+                "sequence flee -> Failure",
+                "condition needs_reload -> Failure",
+                "invert -> Success",
+                "action shoot -> Success",
+                "sequence engage -> Success",
+                "selector root -> Success",
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_names_are_rejected() {
+        assert_eq!(
+            parse_error("selector\n    condition hears_enemy\n"),
+            Some("line 2: unknown condition 'hears_enemy'".to_owned())
+        );
+        assert_eq!(
+            parse_error("selector\n    action dance\n"),
+            Some("line 2: unknown action 'dance'".to_owned())
+        );
+        assert_eq!(
+            parse_error("parallel\n    action shoot\n"),
+            Some("line 1: invalid node 'parallel'".to_owned())
+        );
+    }
+
+    #[test]
+    fn bad_indentation_is_rejected() {
+        assert_eq!(
+            parse_error("selector\n    action shoot\n  action patrol\n"),
+            Some("line 3: inconsistent indentation".to_owned())
+        );
+        assert_eq!(
+            parse_error("action shoot\naction patrol\n"),
+            Some("line 2: tree must have exactly one root".to_owned())
+        );
+        assert_eq!(
+            parse_error("action shoot\n    action patrol\n"),
+            Some("line 1: 'shoot' cannot have children".to_owned())
+        );
+        assert_eq!(
+            parse_error("sequence\naction shoot\n"),
+            Some("line 1: composite node must have children".to_owned())
+        );
+    }
+
+    #[test]
+    fn shipped_behavior_is_valid() {
+        assert!(BehaviorTree::load(crate::BEHAVIOR_PATH).is_ok());
+    }
+}
@@ -0,0 +1,437 @@
+use std::sync::{mpsc::Sender, Arc, RwLock};
+
+use rg3d::{
+    core::{
+        algebra::{Matrix4, UnitQuaternion, Vector3},
+        color::Color,
+        pool::Handle,
+        rand::{self, rngs::ThreadRng, Rng},
+    },
+    physics::{dynamics::RigidBodyBuilder, geometry::ColliderBuilder},
+    renderer::surface::{SurfaceBuilder, SurfaceSharedData},
+    scene::{
+        base::BaseBuilder, mesh::MeshBuilder, node::Node, transform::TransformBuilder,
+        ColliderHandle, RigidBodyHandle, Scene,
+    },
+};
+
+use crate::{
+    behavior::{Action, Agent, BehaviorTree, Condition, Status},
+    cover::CoverPoints,
+    difficulty::Difficulty,
+    hitbox::Hitboxes,
+    layers,
+    message::Message,
+    navmesh::{Navmesh, PathFollower},
+    perception::{Noise, Perception, Target},
+    weapon::Weapon,
+};
+
+pub const MAX_HEALTH: f32 = 100.0;
+
+const MOVE_SPEED: f32 = 1.0;
+
+// Height of bot's eyes above the center of its capsule.
+const EYE_HEIGHT: f32 = 0.35;
+
+// Distance an enemy has to move away from the bot's destination before the bot finds a new
+// path to it.
+const REPATH_DISTANCE: f32 = 1.0;
+
+// Part of the health below which a bot without any aggression retreats, the most aggressive
+// ones never do.
+const RETREAT_HEALTH: f32 = 0.6;
+
+// Aim error halves every that many seconds while a bot keeps its enemy in sight...
+const AIM_HALF_LIFE: f32 = 0.5;
+// ...but never gets lower than this part of the initial error.
+const MIN_AIM_ERROR: f32 = 0.2;
+
+// Distance from the enemy at which a retreating bot feels safe.
+const RETREAT_DISTANCE: f32 = 10.0;
+
+// Amount of random places from which a retreating bot picks the farthest one.
+const RETREAT_CANDIDATES: usize = 8;
+
+// Bot looks for cover within this distance from itself.
+const COVER_SEARCH_RADIUS: f32 = 8.0;
+
+pub struct Bot {
+    pub name: String,
+    // Bots added later have greater numbers, pool slots of removed bots are reused so their
+    // handles tell nothing about that.
+    pub spawn_number: u32,
+    pub pivot: Handle<Node>,
+    pub weapon_pivot: Handle<Node>,
+    pub weapon: Handle<Weapon>,
+    pub rigid_body: RigidBodyHandle,
+    pub collider: ColliderHandle,
+    pub hitboxes: Hitboxes,
+    pub health: f32,
+    pub follower: PathFollower,
+    pub perception: Perception,
+    pub difficulty: Difficulty,
+    // Angle around vertical axis the bot looks at, it turns with limited speed.
+    pub yaw: f32,
+    // Time in seconds the bot sees its current enemy.
+    tracking_time: f32,
+    pub sender: Sender<Message>,
+}
+
+// Everything a bot needs to carry out decisions of its behavior tree.
+struct BotAgent<'a> {
+    bot: &'a mut Bot,
+    scene: &'a Scene,
+    navmesh: &'a Navmesh,
+    covers: &'a mut CoverPoints,
+    weapon: &'a Weapon,
+    rng: ThreadRng,
+}
+
+impl<'a> BotAgent<'a> {
+    // Sets new path only if the current one leads somewhere else, path search is not cheap.
+    fn go_to(&mut self, goal: Vector3<f32>) {
+        if self
+            .bot
+            .follower
+            .destination()
+            .is_none_or(|destination| (destination - goal).norm() > REPATH_DISTANCE)
+        {
+            // Goal may be somewhere off the navmesh (enemy jumps or flies), go straight to it
+            // then.
+            let path = self
+                .navmesh
+                .find_path(self.bot.position(self.scene), goal)
+                .unwrap_or_else(|| vec![goal]);
+            self.bot.follower.set_path(path);
+        }
+    }
+}
+
+impl<'a> Agent for BotAgent<'a> {
+    fn check(&self, condition: Condition) -> bool {
+        match condition {
+            Condition::SeesEnemy => self.bot.perception.visible_target().is_some(),
+            Condition::RemembersEnemy => self.bot.perception.memory().is_some(),
+            Condition::LowHealth => {
+                self.bot.health
+                    < MAX_HEALTH * RETREAT_HEALTH * (1.0 - self.bot.difficulty.aggression)
+            }
+            Condition::Aggressive => self.bot.difficulty.aggression > 0.5,
+            Condition::HasAmmo => self.weapon.ammo() > 0 && !self.weapon.is_reloading(),
+            Condition::NeedsReload => self.weapon.is_reloading() || self.weapon.can_reload(),
+        }
+    }
+
+    fn act(&mut self, action: Action) -> Status {
+        // Going anywhere else than a cover means leaving it.
+        if let Action::Investigate | Action::Patrol | Action::Retreat = action {
+            self.covers.release(&self.bot.name);
+        }
+        match action {
+            Action::Hold => {
+                self.bot.follower.set_path(Vec::new());
+                Status::Success
+            }
+            Action::Shoot => {
+                if self.bot.perception.visible_target().is_none() {
+                    return Status::Failure;
+                }
+                // Nobody shoots the very moment they see an enemy.
+                if self.bot.tracking_time < self.bot.difficulty.reaction_time {
+                    return Status::Running;
+                }
+                // Weapon decides whether it can shoot right now, the same as for the player.
+                self.bot
+                    .sender
+                    .send(Message::ShootWeapon {
+                        weapon: self.bot.weapon,
+                    })
+                    .unwrap();
+                Status::Running
+            }
+            Action::Reload => {
+                if self.weapon.is_reloading() {
+                    Status::Running
+                } else if self.weapon.can_reload() {
+                    self.bot
+                        .sender
+                        .send(Message::ReloadWeapon {
+                            weapon: self.bot.weapon,
+                        })
+                        .unwrap();
+                    Status::Running
+                } else if self.weapon.ammo() > 0 {
+                    Status::Success
+                } else {
+                    Status::Failure
+                }
+            }
+            Action::Investigate => match self.bot.perception.memory() {
+                Some(memory) => {
+                    let goal = memory.position;
+                    self.go_to(goal);
+                    if self.bot.follower.is_finished() {
+                        Status::Success
+                    } else {
+                        Status::Running
+                    }
+                }
+                None => Status::Failure,
+            },
+            Action::Patrol => {
+                if self.bot.follower.is_finished() {
+                    if let Some(goal) = self.navmesh.random_point(&mut self.rng) {
+                        self.go_to(goal);
+                    }
+                }
+                Status::Running
+            }
+            Action::Retreat => {
+                let threat = match self.bot.perception.memory() {
+                    Some(memory) => memory.position,
+                    None => return Status::Failure,
+                };
+                let is_safe = |point: Vector3<f32>| (point - threat).norm() >= RETREAT_DISTANCE;
+                if is_safe(self.bot.position(self.scene)) {
+                    return Status::Success;
+                }
+                if self.bot.follower.is_finished()
+                    || self.bot.follower.destination().is_none_or(|d| !is_safe(d))
+                {
+                    let rng = &mut self.rng;
+                    let navmesh = self.navmesh;
+                    if let Some(goal) = (0..RETREAT_CANDIDATES)
+                        .filter_map(|_| navmesh.random_point(rng))
+                        .max_by(|a, b| (a - threat).norm().total_cmp(&(b - threat).norm()))
+                    {
+                        self.go_to(goal);
+                    }
+                }
+                Status::Running
+            }
+            Action::TakeCover => {
+                let threat = match self.bot.perception.memory() {
+                    Some(memory) => memory.position,
+                    None => return Status::Failure,
+                };
+                // Keep the current cover while it still protects from the enemy.
+                if let Some(index) = self.covers.claimed_by(&self.bot.name) {
+                    let cover = self.covers.get(index);
+                    if cover.protects_from(threat) {
+                        let position = cover.position;
+                        self.go_to(position);
+                        return Status::Success;
+                    }
+                    self.covers.release(&self.bot.name);
+                }
+                let from = self.bot.position(self.scene);
+                match self.covers.find_best(
+                    self.navmesh,
+                    from,
+                    threat,
+                    COVER_SEARCH_RADIUS,
+                    &self.bot.name,
+                ) {
+                    Some((index, path)) if self.covers.claim(index, &self.bot.name) => {
+                        self.bot.follower.set_path(path);
+                        Status::Success
+                    }
+                    _ => Status::Failure,
+                }
+            }
+        }
+    }
+}
+
+impl Bot {
+    pub fn new(
+        scene: &mut Scene,
+        name: String,
+        position: Vector3<f32>,
+        fov: f32,
+        difficulty: Difficulty,
+        sender: Sender<Message>,
+        layer: u16,
+    ) -> Self {
+        // There is no character model yet, so bot is just a cylinder of the same size as
+        // its capsule.
+        let body_mesh = MeshBuilder::new(BaseBuilder::new())
+            .with_surfaces(vec![SurfaceBuilder::new(Arc::new(RwLock::new(
+                SurfaceSharedData::make_cylinder(
+                    16,
+                    0.2,
+                    0.9,
+                    true,
+                    Matrix4::new_translation(&Vector3::new(0.0, -0.45, 0.0)),
+                ),
+            )))
+            .with_color(Color::opaque(200, 60, 60))
+            .build()])
+            .build(&mut scene.graph);
+        // Bot holds its weapon at the same height as the player does.
+        let weapon_pivot = BaseBuilder::new()
+            .with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(-0.1, 0.2, 0.2))
+                    .build(),
+            )
+            .build(&mut scene.graph);
+        let pivot = BaseBuilder::new()
+            .with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(position)
+                    .build(),
+            )
+            .with_children(&[body_mesh, weapon_pivot])
+            .build(&mut scene.graph);
+        let rigid_body = scene.physics.add_body(
+            RigidBodyBuilder::new_dynamic()
+                .lock_rotations()
+                .translation(position.x, position.y, position.z)
+                .build(),
+        );
+        let collider = scene.physics.add_collider(
+            ColliderBuilder::capsule_y(0.25, 0.2)
+                .collision_groups(layers::collider(layer))
+                .build(),
+            rigid_body,
+        );
+        scene.physics_binder.bind(pivot, rigid_body);
+        let hitboxes = Hitboxes::attach(scene, pivot, layer);
+        Self {
+            name,
+            spawn_number: 0,
+            pivot,
+            weapon_pivot,
+            weapon: Default::default(),
+            rigid_body,
+            collider,
+            hitboxes,
+            health: MAX_HEALTH,
+            follower: Default::default(),
+            perception: Perception::new(fov, difficulty.view_distance),
+            difficulty,
+            yaw: 0.0,
+            tracking_time: 0.0,
+            sender,
+        }
+    }
+
+    pub fn position(&self, scene: &Scene) -> Vector3<f32> {
+        scene.graph[self.pivot].global_position()
+    }
+
+    // Bot looks where its pivot looks, so it sees what is in front of it.
+    pub fn perceive(&mut self, dt: f32, scene: &Scene, targets: &[Target], noises: &[Noise]) {
+        let eye = self.position(scene) + Vector3::new(0.0, EYE_HEIGHT, 0.0);
+        let look = scene.graph[self.pivot].look_vector();
+        self.perception.update(
+            dt,
+            &scene.physics,
+            eye,
+            look,
+            self.collider,
+            targets,
+            noises,
+        );
+        if self.perception.visible_target().is_some() {
+            self.tracking_time += dt;
+        } else {
+            self.tracking_time = 0.0;
+        }
+    }
+
+    // Current aim error in radians, it is the largest right after an enemy is spotted.
+    pub fn aim_error(&self) -> f32 {
+        let factor = 0.5f32.powf(self.tracking_time / AIM_HALF_LIFE);
+        self.difficulty.aim_error * factor.max(MIN_AIM_ERROR)
+    }
+
+    // Runs behavior tree once, its actions change the path of the bot or send messages to
+    // the game.
+    pub fn think(
+        &mut self,
+        behavior: &BehaviorTree,
+        scene: &Scene,
+        navmesh: &Navmesh,
+        covers: &mut CoverPoints,
+        weapon: &Weapon,
+        trace: Option<&mut Vec<String>>,
+    ) -> Status {
+        let mut agent = BotAgent {
+            bot: self,
+            scene,
+            navmesh,
+            covers,
+            weapon,
+            rng: rand::thread_rng(),
+        };
+        behavior.tick(&mut agent, trace)
+    }
+
+    // Bot is moved the same way as the player - by setting velocity of its body, gravity is
+    // left untouched. It turns toward the enemy it sees or the direction it walks otherwise.
+    pub fn update(&mut self, dt: f32, scene: &mut Scene) {
+        let position = self.position(scene);
+        let velocity = self.follower.steer(position, MOVE_SPEED);
+        let visible_target = self.perception.visible_target();
+        let facing = visible_target.map_or(velocity, |target| target - position);
+        if facing.x != 0.0 || facing.z != 0.0 {
+            let desired = facing.x.atan2(facing.z);
+            // Shortest way to the desired angle.
+            let delta = (desired - self.yaw + std::f32::consts::PI)
+                .rem_euclid(std::f32::consts::TAU)
+                - std::f32::consts::PI;
+            let max_turn = self.difficulty.turn_speed * dt;
+            self.yaw += delta.clamp(-max_turn, max_turn);
+        }
+
+        // Body turns only around vertical axis, weapon is tilted up or down to aim. Aim
+        // error moves the weapon randomly off the target.
+        let (yaw_error, pitch_error) = if visible_target.is_some() {
+            let mut rng = rand::thread_rng();
+            let error = self.aim_error();
+            (
+                rng.gen_range(-1.0..=1.0) * error,
+                rng.gen_range(-1.0..=1.0) * error,
+            )
+        } else {
+            (0.0, 0.0)
+        };
+        let pitch = visible_target.map_or(0.0, |target| {
+            let delta = target - scene.graph[self.weapon_pivot].global_position();
+            -delta.y.atan2(Vector3::new(delta.x, 0.0, delta.z).norm())
+        });
+        scene.graph[self.weapon_pivot]
+            .local_transform_mut()
+            .set_rotation(
+                UnitQuaternion::from_axis_angle(&Vector3::y_axis(), yaw_error)
+                    * UnitQuaternion::from_axis_angle(&Vector3::x_axis(), pitch + pitch_error),
+            );
+
+        let body = scene
+            .physics
+            .bodies
+            .get_mut(self.rigid_body.into())
+            .unwrap();
+        body.set_linvel(Vector3::new(velocity.x, body.linvel().y, velocity.z), true);
+        let mut position = *body.position();
+        position.rotation = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), self.yaw);
+        body.set_position(position, true);
+
+        self.hitboxes.update(scene);
+    }
+
+    // Returns true if the damage was lethal.
+    pub fn damage(&mut self, amount: f32) -> bool {
+        self.health = (self.health - amount).max(0.0);
+        self.health <= 0.0
+    }
+
+    pub fn clean_up(&mut self, scene: &mut Scene) {
+        // This also removes rigid body bound to the pivot and the weapon model.
+        scene.remove_node(self.pivot);
+        self.hitboxes.remove(scene);
+    }
+}
@@ -0,0 +1,355 @@
+use rg3d::core::algebra::Vector3;
+
+use crate::{
+    console::{CommandRegistry, CvarValue},
+    difficulty, hitbox,
+    message::Message,
+};
+
+// Items that can be given by `give` command.
+const GIVE_ITEMS: &[&str] = &["health", "armor", "ammo"];
+
+// Registers every game-specific command and cvar. Commands do not touch the game directly,
+// instead they send messages which will be handled by the game on next update.
+pub fn register(registry: &mut CommandRegistry) {
+    let cvars = registry.cvars_mut();
+    cvars.register(
+        "sv_tickrate",
+        CvarValue::Int(60),
+        "amount of game logic updates per second",
+    );
+    cvars.register(
+        "timescale",
+        CvarValue::Float(1.0),
+        "speed of the simulation, 1.0 is normal",
+    );
+    cvars.register(
+        "cl_pause_on_focus_loss",
+        CvarValue::Bool(true),
+        "pause the game when the window loses focus",
+    );
+    cvars.register(
+        "cl_tracer_palette",
+        CvarValue::String("normal".to_owned()),
+        "colors of tracers: normal or colorblind",
+    );
+    cvars.register(
+        "cl_shell_lifetime",
+        CvarValue::Float(5.0),
+        "time in seconds spent shell casings stay on the ground",
+    );
+    cvars.register(
+        "cl_max_shells",
+        CvarValue::Int(64),
+        "maximum amount of spent shell casings, the oldest ones are removed first",
+    );
+    cvars.register(
+        "cl_max_decals",
+        CvarValue::Int(200),
+        "maximum amount of bullet holes and scorch marks, the oldest ones are removed first",
+    );
+    cvars.register(
+        "g_impact_force",
+        CvarValue::Float(10.0),
+        "force applied to a body hit by a bullet",
+    );
+    cvars.register(
+        "g_explosion_radius",
+        CvarValue::Float(5.0),
+        "radius of an explosion, unless given by whatever explodes",
+    );
+    cvars.register(
+        "g_explosion_impulse",
+        CvarValue::Float(20.0),
+        "impulse applied to a body at the center of an explosion",
+    );
+    cvars.register(
+        "g_explosion_damage",
+        CvarValue::Float(100.0),
+        "damage dealt at the center of an explosion, unless given by whatever explodes",
+    );
+    cvars.register(
+        "g_explosion_falloff",
+        CvarValue::String("linear".to_owned()),
+        "how explosion impulse and damage go down with distance: none, linear or quadratic",
+    );
+    cvars.register(
+        "g_grenade_fuse",
+        CvarValue::Float(3.0),
+        "seconds from pulling the pin of a grenade to the blast",
+    );
+    cvars.register(
+        "g_grenade_throw_speed",
+        CvarValue::Float(12.0),
+        "speed a grenade is thrown with, besides the speed of the thrower",
+    );
+    cvars.register(
+        "g_grenade_restitution",
+        CvarValue::Float(0.4),
+        "how much of its speed a grenade keeps bouncing off something, from 0 to 1",
+    );
+    cvars.register(
+        "weapon_fire_interval",
+        CvarValue::Float(0.1),
+        "minimal time in seconds between two shots of weapons which do not set their own",
+    );
+    cvars.register(
+        "weapon_recoil_rise",
+        CvarValue::Float(0.00625),
+        "how much weapon model goes up on each shot",
+    );
+    cvars.register(
+        "weapon_recoil_kick",
+        CvarValue::Float(0.025),
+        "how much weapon model goes back on each shot",
+    );
+    cvars.register(
+        "weapon_damage",
+        CvarValue::Float(20.0),
+        "damage of a single bullet of weapons which do not set their own",
+    );
+    cvars.register(
+        "weapon_penetration",
+        CvarValue::Float(20.0),
+        "how much material resistance a bullet goes through before it stops",
+    );
+    cvars.register(
+        "weapon_pellet_seed",
+        CvarValue::Int(0),
+        "seed of pellet patterns of shotguns, the same seed gives the same pattern every round",
+    );
+    cvars.register(
+        "sv_hostname",
+        CvarValue::String("Shooter".to_owned()),
+        "server name shown in LAN server browser",
+    );
+    cvars.register(
+        "sv_lan_visible",
+        CvarValue::Bool(true),
+        "answer LAN discovery queries",
+    );
+    cvars.register(
+        "sv_maxbots",
+        CvarValue::Int(16),
+        "maximum amount of bots in the match",
+    );
+    cvars.register(
+        "bot_spawn_radius",
+        CvarValue::Float(3.0),
+        "bots are spawned within this distance from the center of the map",
+    );
+    cvars.register(
+        "bot_fov",
+        CvarValue::Float(90.0),
+        "angle in degrees of the vision cone of new bots",
+    );
+    cvars.register(
+        "bot_trace",
+        CvarValue::String(String::new()),
+        "name of the bot whose behavior tree is printed to the console on every tick",
+    );
+    cvars.register(
+        "g_gunshot_noise_radius",
+        CvarValue::Float(25.0),
+        "distance at which bots hear gunshots",
+    );
+    cvars.register(
+        "g_footstep_noise_radius",
+        CvarValue::Float(4.0),
+        "distance at which bots hear footsteps",
+    );
+
+    difficulty::register_cvars(cvars);
+    hitbox::register_cvars(cvars);
+
+    registry.register(
+        "kick",
+        "kick <name> - removes a player from the match",
+        |ctx, args| match args {
+            [name] => {
+                ctx.sender
+                    .send(Message::Kick {
+                        name: name.to_string(),
+                    })
+                    .unwrap();
+                Ok(format!("kicking {}", name))
+            }
+            _ => Err("usage: kick <name>".to_owned()),
+        },
+    );
+    registry.register(
+        "map",
+        "map <name> - loads assets/models/<name>.rgs",
+        |ctx, args| match args {
+            [name] => {
+                ctx.sender
+                    .send(Message::ChangeMap {
+                        name: name.to_string(),
+                    })
+                    .unwrap();
+                Ok(format!("changing map to {}", name))
+            }
+            _ => Err("usage: map <name>".to_owned()),
+        },
+    );
+    registry.register(
+        "spawn_bot",
+        "spawn_bot - adds a bot to the match",
+        |ctx, args| {
+            if !args.is_empty() {
+                return Err("usage: spawn_bot".to_owned());
+            }
+            ctx.sender.send(Message::AddBot).unwrap();
+            Ok("adding bot".to_owned())
+        },
+    );
+    registry.register(
+        "remove_bot",
+        "remove_bot [name] - removes given or last added bot",
+        |ctx, args| match args {
+            [] | [_] => {
+                ctx.sender
+                    .send(Message::RemoveBot {
+                        name: args.first().map(|n| n.to_string()),
+                    })
+                    .unwrap();
+                Ok("removing bot".to_owned())
+            }
+            _ => Err("usage: remove_bot [name]".to_owned()),
+        },
+    );
+    registry.register(
+        "bot_perception",
+        "bot_perception [name] - prints what given or every bot sees, hears and remembers",
+        |ctx, args| match args {
+            [] | [_] => {
+                ctx.sender
+                    .send(Message::DumpPerception {
+                        name: args.first().map(|n| n.to_string()),
+                    })
+                    .unwrap();
+                Ok(String::new())
+            }
+            _ => Err("usage: bot_perception [name]".to_owned()),
+        },
+    );
+    registry.register("god", "god - toggles invulnerability", |ctx, args| {
+        if !args.is_empty() {
+            return Err("usage: god".to_owned());
+        }
+        ctx.sender.send(Message::ToggleGod).unwrap();
+        Ok(String::new())
+    });
+    registry.register(
+        "noclip",
+        "noclip - toggles flying through walls",
+        |ctx, args| {
+            if !args.is_empty() {
+                return Err("usage: noclip".to_owned());
+            }
+            ctx.sender.send(Message::ToggleNoclip).unwrap();
+            Ok(String::new())
+        },
+    );
+    registry.register(
+        "give",
+        "give <item> [amount] - gives an item, items: health, armor, ammo",
+        |ctx, args| {
+            let (item, amount) = match args {
+                [item] => (item, None),
+                [item, amount] => (
+                    item,
+                    Some(
+                        amount
+                            .parse::<f32>()
+                            .map_err(|_| format!("invalid amount '{}'", amount))?,
+                    ),
+                ),
+                _ => return Err("usage: give <item> [amount]".to_owned()),
+            };
+            if !GIVE_ITEMS.contains(item) {
+                return Err(format!("unknown item '{}'", item));
+            }
+            ctx.sender
+                .send(Message::Give {
+                    item: item.to_string(),
+                    amount,
+                })
+                .unwrap();
+            Ok(String::new())
+        },
+    );
+    registry.register(
+        "weapon",
+        "weapon <name> - gives a weapon from assets/weapons.txt instead of the current one",
+        |ctx, args| match args {
+            [name] => {
+                ctx.sender
+                    .send(Message::SwitchWeapon {
+                        name: name.to_string(),
+                    })
+                    .unwrap();
+                Ok(String::new())
+            }
+            _ => Err("usage: weapon <name>".to_owned()),
+        },
+    );
+    registry.register(
+        "weapon_falloff",
+        "weapon_falloff [name] - prints damage and impulse by distance of given or current weapon",
+        |ctx, args| match args {
+            [] | [_] => {
+                ctx.sender
+                    .send(Message::DumpFalloff {
+                        name: args.first().map(|n| n.to_string()),
+                    })
+                    .unwrap();
+                Ok(String::new())
+            }
+            _ => Err("usage: weapon_falloff [name]".to_owned()),
+        },
+    );
+    registry.register(
+        "explode",
+        "explode <x> <y> <z> [radius] [damage] - makes an explosion at given point",
+        |ctx, args| {
+            if !(3..=5).contains(&args.len()) {
+                return Err("usage: explode <x> <y> <z> [radius] [damage]".to_owned());
+            }
+            let numbers = args
+                .iter()
+                .map(|arg| {
+                    arg.parse::<f32>()
+                        .map_err(|_| format!("invalid number '{}'", arg))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            ctx.sender
+                .send(Message::Explode {
+                    center: Vector3::new(numbers[0], numbers[1], numbers[2]),
+                    radius: numbers.get(3).copied(),
+                    damage: numbers.get(4).copied(),
+                })
+                .unwrap();
+            Ok(String::new())
+        },
+    );
+    registry.register(
+        "hurt",
+        "hurt <amount> - damages the player",
+        |ctx, args| match args {
+            [amount] => {
+                let amount = amount
+                    .parse()
+                    .map_err(|_| format!("invalid amount '{}'", amount))?;
+                ctx.sender
+                    .send(Message::DamagePlayer {
+                        amount,
+                        region: None,
+                    })
+                    .unwrap();
+                Ok(String::new())
+            }
+            _ => Err("usage: hurt <amount>".to_owned()),
+        },
+    );
+}
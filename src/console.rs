@@ -0,0 +1,461 @@
+use std::{collections::BTreeMap, fmt, fs, path::Path, sync::mpsc::Sender};
+
+use crate::message::Message;
+
+// Protects from scripts that execute themselves.
+const MAX_EXEC_DEPTH: usize = 8;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CvarValue {
+    Bool(bool),
+    Int(i32),
+    Float(f32),
+    String(String),
+}
+
+impl CvarValue {
+    // Parses text as a value of the same type as self.
+    fn parse_same(&self, text: &str) -> Result<Self, String> {
+        match self {
+            CvarValue::Bool(_) => match text {
+                "1" | "true" | "on" => Ok(CvarValue::Bool(true)),
+                "0" | "false" | "off" => Ok(CvarValue::Bool(false)),
+                _ => Err(format!("expected boolean, got '{}'", text)),
+            },
+            CvarValue::Int(_) => text
+                .parse()
+                .map(CvarValue::Int)
+                .map_err(|_| format!("expected integer, got '{}'", text)),
+            CvarValue::Float(_) => text
+                .parse()
+                .map(CvarValue::Float)
+                .map_err(|_| format!("expected number, got '{}'", text)),
+            CvarValue::String(_) => Ok(CvarValue::String(text.to_owned())),
+        }
+    }
+}
+
+impl fmt::Display for CvarValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CvarValue::Bool(v) => write!(f, "{}", *v as u8),
+            CvarValue::Int(v) => write!(f, "{}", v),
+            CvarValue::Float(v) => write!(f, "{}", v),
+            CvarValue::String(v) => write!(f, "\"{}\"", v),
+        }
+    }
+}
+
+pub struct Cvar {
+    pub value: CvarValue,
+    pub default: CvarValue,
+    pub description: String,
+}
+
+/// A set of named, typed variables that can be changed at runtime.
+#[derive(Default)]
+pub struct Cvars {
+    vars: BTreeMap<String, Cvar>,
+}
+
+impl Cvars {
+    pub fn register(&mut self, name: &str, default: CvarValue, description: &str) {
+        self.vars.insert(
+            name.to_owned(),
+            Cvar {
+                value: default.clone(),
+                default,
+                description: description.to_owned(),
+            },
+        );
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Cvar> {
+        self.vars.get(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Cvar)> {
+        self.vars.iter()
+    }
+
+    pub fn set(&mut self, name: &str, text: &str) -> Result<(), String> {
+        let cvar = self
+            .vars
+            .get_mut(name)
+            .ok_or_else(|| format!("unknown cvar '{}'", name))?;
+        cvar.value = cvar.value.parse_same(text)?;
+        Ok(())
+    }
+
+    pub fn reset(&mut self, name: &str) -> Result<(), String> {
+        let cvar = self
+            .vars
+            .get_mut(name)
+            .ok_or_else(|| format!("unknown cvar '{}'", name))?;
+        cvar.value = cvar.default.clone();
+        Ok(())
+    }
+
+    // Getters of typed values are called with names written in code, so a missing cvar or
+    // one of another type is a typo. Debug builds stop on it, release builds report it and
+    // give zero, false or empty string.
+
+    pub fn bool(&self, name: &str) -> bool {
+        match self.vars.get(name).map(|c| &c.value) {
+            Some(CvarValue::Bool(v)) => *v,
+            _ => mismatch(name, "boolean"),
+        }
+    }
+
+    pub fn int(&self, name: &str) -> i32 {
+        match self.vars.get(name).map(|c| &c.value) {
+            Some(CvarValue::Int(v)) => *v,
+            _ => mismatch(name, "integer"),
+        }
+    }
+
+    pub fn float(&self, name: &str) -> f32 {
+        match self.vars.get(name).map(|c| &c.value) {
+            Some(CvarValue::Float(v)) => *v,
+            _ => mismatch(name, "float"),
+        }
+    }
+
+    pub fn string(&self, name: &str) -> &str {
+        match self.vars.get(name).map(|c| &c.value) {
+            Some(CvarValue::String(v)) => v,
+            _ => mismatch(name, "string"),
+        }
+    }
+}
+
+fn mismatch<T: Default>(name: &str, kind: &str) -> T {
+    let message = format!("there is no {} cvar '{}'", kind, name);
+    if cfg!(debug_assertions) {
+        panic!("{}", message);
+    }
+    println!("{}", message);
+    T::default()
+}
+
+pub struct CommandContext<'a> {
+    pub cvars: &'a mut Cvars,
+    pub sender: &'a Sender<Message>,
+}
+
+/// Every command either returns text to show to the caller or an error message.
+pub type CommandHandler = Box<dyn Fn(&mut CommandContext, &[&str]) -> Result<String, String>>;
+
+struct Command {
+    help: String,
+    handler: CommandHandler,
+}
+
+/// Single place where every console command and cvar lives. It knows nothing about the
+/// way commands are delivered, so the same registry is driven by RCON and local console.
+pub struct CommandRegistry {
+    commands: BTreeMap<String, Command>,
+    cvars: Cvars,
+    sender: Sender<Message>,
+    exec_depth: usize,
+    // Set while a command from RCON runs, scripts are not executed for remote callers
+    // because their lines would be echoed back in errors.
+    remote: bool,
+}
+
+impl CommandRegistry {
+    pub fn new(sender: Sender<Message>) -> Self {
+        let mut registry = Self {
+            commands: Default::default(),
+            cvars: Default::default(),
+            sender,
+            exec_depth: 0,
+            remote: false,
+        };
+        registry.register("cvarlist", "cvarlist - prints every cvar", |ctx, _| {
+            Ok(ctx
+                .cvars
+                .iter()
+                .map(|(name, cvar)| format!("{} = {} - {}", name, cvar.value, cvar.description))
+                .collect::<Vec<_>>()
+                .join("\n"))
+        });
+        registry.register(
+            "set",
+            "set <cvar> <value> - changes cvar",
+            |ctx, args| match args {
+                [name, value] => ctx.cvars.set(name, value).map(|_| String::new()),
+                _ => Err("usage: set <cvar> <value>".to_owned()),
+            },
+        );
+        registry.register(
+            "reset",
+            "reset <cvar> - restores default value",
+            |ctx, args| match args {
+                [name] => ctx.cvars.reset(name).map(|_| String::new()),
+                _ => Err("usage: reset <cvar>".to_owned()),
+            },
+        );
+        registry
+    }
+
+    pub fn register<F>(&mut self, name: &str, help: &str, handler: F)
+    where
+        F: Fn(&mut CommandContext, &[&str]) -> Result<String, String> + 'static,
+    {
+        self.commands.insert(
+            name.to_owned(),
+            Command {
+                help: help.to_owned(),
+                handler: Box::new(handler),
+            },
+        );
+    }
+
+    pub fn cvars(&self) -> &Cvars {
+        &self.cvars
+    }
+
+    pub fn cvars_mut(&mut self) -> &mut Cvars {
+        &mut self.cvars
+    }
+
+    /// Executes command line, commands in it are separated by `;`. Name of a cvar alone prints
+    /// its value, name of a cvar followed by a value changes it. Execution does not stop on
+    /// errors, all of them are reported at once.
+    pub fn execute(&mut self, line: &str) -> Result<String, String> {
+        let mut commands = tokenize(line);
+        if commands.len() <= 1 {
+            return self.execute_tokens(&commands.pop().unwrap_or_default());
+        }
+        let mut output = Vec::new();
+        let mut errors = Vec::new();
+        for tokens in commands {
+            match self.execute_tokens(&tokens) {
+                Ok(text) if !text.is_empty() => output.push(text),
+                Ok(_) => (),
+                Err(e) => errors.push(e),
+            }
+        }
+        if errors.is_empty() {
+            Ok(output.join("\n"))
+        } else {
+            Err(errors.join("\n"))
+        }
+    }
+
+    /// Executes command line that came from a remote caller, same as `execute` except that
+    /// `exec` is refused.
+    pub fn execute_remote(&mut self, line: &str) -> Result<String, String> {
+        self.remote = true;
+        let result = self.execute(line);
+        self.remote = false;
+        result
+    }
+
+    fn execute_tokens(&mut self, tokens: &[String]) -> Result<String, String> {
+        let tokens = tokens.iter().map(|t| t.as_str()).collect::<Vec<_>>();
+        let (name, args) = match tokens.split_first() {
+            Some(pair) => pair,
+            None => return Ok(String::new()),
+        };
+        if *name == "exec" {
+            if self.remote {
+                return Err("exec is not allowed from remote console".to_owned());
+            }
+            return match args {
+                [path] => self.exec_script(path),
+                _ => Err("usage: exec <file>".to_owned()),
+            };
+        }
+        if *name == "help" {
+            return Ok(std::iter::once("exec <file> - executes config script")
+                .chain(self.commands.values().map(|c| c.help.as_str()))
+                .collect::<Vec<_>>()
+                .join("\n"));
+        }
+        if let Some(command) = self.commands.get(*name) {
+            let mut context = CommandContext {
+                cvars: &mut self.cvars,
+                sender: &self.sender,
+            };
+            (command.handler)(&mut context, args)
+        } else if let Some(cvar) = self.cvars.get(name) {
+            match args {
+                [] => Ok(format!("{} = {}", name, cvar.value)),
+                [value] => self.cvars.set(name, value).map(|_| String::new()),
+                _ => Err(format!("usage: {} [value]", name)),
+            }
+        } else {
+            Err(format!("unknown command '{}'", name))
+        }
+    }
+}
+
+impl CommandRegistry {
+    /// Executes every line of a config script. Empty lines and lines starting with `//` or
+    /// `#` are ignored. Execution does not stop on errors, all of them are reported at once.
+    pub fn exec_script<P: AsRef<Path>>(&mut self, path: P) -> Result<String, String> {
+        let path = path.as_ref();
+        if self.exec_depth >= MAX_EXEC_DEPTH {
+            return Err(format!("{}: exec nesting is too deep", path.display()));
+        }
+        let script = fs::read_to_string(path)
+            .map_err(|e| format!("unable to read {}: {}", path.display(), e))?;
+        self.exec_depth += 1;
+        let mut output = Vec::new();
+        let mut errors = Vec::new();
+        for (n, line) in script.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("//") || line.starts_with('#') {
+                continue;
+            }
+            match self.execute(line) {
+                Ok(text) if !text.is_empty() => output.push(text),
+                Ok(_) => (),
+                Err(e) => errors.push(format!("{}:{}: {}", path.display(), n + 1, e)),
+            }
+        }
+        self.exec_depth -= 1;
+        if errors.is_empty() {
+            Ok(output.join("\n"))
+        } else {
+            Err(errors.join("\n"))
+        }
+    }
+}
+
+// Splits command line into commands by `;` and every command into tokens by whitespace,
+// double quotes groups several words (and `;`) into one token. Empty commands are left out.
+fn tokenize(line: &str) -> Vec<Vec<String>> {
+    let mut commands = Vec::new();
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_token = false;
+    for c in line.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_token = true;
+            }
+            c if (c.is_whitespace() || c == ';') && !in_quotes => {
+                if has_token {
+                    tokens.push(std::mem::take(&mut current));
+                    has_token = false;
+                }
+                if c == ';' && !tokens.is_empty() {
+                    commands.push(std::mem::take(&mut tokens));
+                }
+            }
+            c => {
+                current.push(c);
+                has_token = true;
+            }
+        }
+    }
+    if has_token {
+        tokens.push(current);
+    }
+    if !tokens.is_empty() {
+        commands.push(tokens);
+    }
+    commands
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    fn registry() -> CommandRegistry {
+        let (sender, _receiver) = mpsc::channel();
+        let mut registry = CommandRegistry::new(sender);
+        let cvars = registry.cvars_mut();
+        cvars.register("god", CvarValue::Bool(false), "");
+        cvars.register("timescale", CvarValue::Float(1.0), "");
+        cvars.register("sv_name", CvarValue::String("Server".to_owned()), "");
+        registry
+    }
+
+    #[test]
+    fn tokenize_splits_words_and_commands() {
+        assert_eq!(tokenize("set  god 1"), vec![vec!["set", "god", "1"]]);
+        assert_eq!(
+            tokenize("sv_name \"Big server\" ; god 1;;timescale 2;"),
+            vec![
+                vec!["sv_name", "Big server"],
+                vec!["god", "1"],
+                vec!["timescale", "2"]
+            ]
+        );
+        assert_eq!(tokenize("say \"a; b\" \"\""), vec![vec!["say", "a; b", ""]]);
+        assert!(tokenize("  ; ").is_empty());
+    }
+
+    #[test]
+    fn cvars_are_set_and_read() {
+        let mut registry = registry();
+        assert_eq!(
+            registry.execute("timescale"),
+            Ok("timescale = 1".to_owned())
+        );
+        registry.execute("set timescale 0.5; god on").unwrap();
+        assert_eq!(registry.cvars().float("timescale"), 0.5);
+        assert!(registry.cvars().bool("god"));
+        assert!(registry.execute("timescale fast").is_err());
+        assert_eq!(registry.cvars().float("timescale"), 0.5);
+        registry.execute("reset timescale").unwrap();
+        assert_eq!(registry.cvars().float("timescale"), 1.0);
+        assert!(registry.execute("set no_such_cvar 1").is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "there is no float cvar 'god'")]
+    fn getter_of_another_type_panics_in_debug() {
+        registry().cvars().float("god");
+    }
+
+    #[test]
+    #[should_panic(expected = "there is no boolean cvar 'no_such_cvar'")]
+    fn getter_of_missing_cvar_panics_in_debug() {
+        registry().cvars().bool("no_such_cvar");
+    }
+
+    #[test]
+    fn remote_callers_can_not_exec() {
+        let mut registry = registry();
+        assert_eq!(
+            registry.execute_remote("god 1; exec /etc/passwd"),
+            Err("exec is not allowed from remote console".to_owned())
+        );
+        assert!(registry.cvars().bool("god"));
+        assert!(registry.execute("exec /no/such/file.cfg").is_err());
+        assert!(!registry.remote);
+    }
+
+    #[test]
+    fn errors_do_not_stop_other_commands() {
+        let mut registry = registry();
+        let errors = registry
+            .execute("god 1; no_such_command; timescale 3")
+            .unwrap_err();
+        assert_eq!(errors, "unknown command 'no_such_command'");
+        assert!(registry.cvars().bool("god"));
+        assert_eq!(registry.cvars().float("timescale"), 3.0);
+    }
+
+    #[test]
+    fn exec_stops_at_max_depth() {
+        let path = std::env::temp_dir().join(format!("console_test_{}.cfg", std::process::id()));
+        std::fs::write(&path, format!("exec \"{}\"\n", path.display())).unwrap();
+        let mut registry = registry();
+        let result = registry.exec_script(&path);
+        std::fs::remove_file(&path).unwrap();
+        let errors = result.unwrap_err();
+        assert!(errors.ends_with("exec nesting is too deep"));
+        // Every script that got to run reports the line of the nested exec.
+        assert_eq!(errors.matches(":1: ").count(), MAX_EXEC_DEPTH);
+        assert_eq!(registry.exec_depth, 0);
+    }
+}
@@ -0,0 +1,221 @@
+use rg3d::{
+    core::{color::Color, pool::Handle},
+    event::{ElementState, Event, VirtualKeyCode, WindowEvent},
+    gui::{
+        border::BorderBuilder,
+        brush::Brush,
+        grid::{Column, GridBuilder, Row},
+        message::{MessageDirection, TextMessage, WidgetMessage},
+        text::TextBuilder,
+        widget::WidgetBuilder,
+        HorizontalAlignment, Thickness, VerticalAlignment,
+    },
+};
+
+use crate::{console::CommandRegistry, Ui, UiNode};
+
+// Console takes this fraction of the screen height.
+const HEIGHT_FRACTION: f32 = 0.4;
+
+// Lines that don't fit on the screen are dropped.
+const MAX_LINES: usize = 256;
+const VISIBLE_LINES: usize = 24;
+
+/// Quake-style drop-down console. It only shows text and collects input line, every command
+/// goes to the command registry.
+pub struct ConsoleUi {
+    root: Handle<UiNode>,
+    output: Handle<UiNode>,
+    input_text: Handle<UiNode>,
+    input: String,
+    lines: Vec<String>,
+    history: Vec<String>,
+    history_position: usize,
+    visible: bool,
+}
+
+impl ConsoleUi {
+    pub fn new(ui: &mut Ui) -> Self {
+        let ctx = &mut ui.build_ctx();
+        let output = TextBuilder::new(WidgetBuilder::new().on_row(0))
+            .with_wrap(true)
+            .with_vertical_text_alignment(VerticalAlignment::Bottom)
+            .build(ctx);
+        let input_text = TextBuilder::new(
+            WidgetBuilder::new()
+                .on_row(1)
+                .with_foreground(Brush::Solid(Color::opaque(255, 255, 0))),
+        )
+        .with_text("> ")
+        .build(ctx);
+        let root = BorderBuilder::new(
+            WidgetBuilder::new()
+                .with_visibility(false)
+                .with_vertical_alignment(VerticalAlignment::Top)
+                .with_horizontal_alignment(HorizontalAlignment::Stretch)
+                .with_background(Brush::Solid(Color::from_rgba(0, 0, 0, 200)))
+                .with_child(
+                    GridBuilder::new(
+                        WidgetBuilder::new()
+                            .with_margin(Thickness::uniform(4.0))
+                            .with_child(output)
+                            .with_child(input_text),
+                    )
+                    .add_row(Row::stretch())
+                    .add_row(Row::strict(20.0))
+                    .add_column(Column::stretch())
+                    .build(ctx),
+                ),
+        )
+        .build(ctx);
+        Self {
+            root,
+            output,
+            input_text,
+            input: Default::default(),
+            lines: Default::default(),
+            history: Default::default(),
+            history_position: 0,
+            visible: false,
+        }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn set_visible(&mut self, ui: &Ui, visible: bool) {
+        self.visible = visible;
+        ui.send_message(WidgetMessage::visibility(
+            self.root,
+            MessageDirection::ToWidget,
+            visible,
+        ));
+    }
+
+    pub fn resize(&self, ui: &Ui, width: f32, height: f32) {
+        ui.send_message(WidgetMessage::width(
+            self.root,
+            MessageDirection::ToWidget,
+            width,
+        ));
+        ui.send_message(WidgetMessage::height(
+            self.root,
+            MessageDirection::ToWidget,
+            height * HEIGHT_FRACTION,
+        ));
+    }
+
+    pub fn print(&mut self, ui: &Ui, text: &str) {
+        for line in text.lines() {
+            println!("{}", line);
+            self.lines.push(line.to_owned());
+        }
+        if self.lines.len() > MAX_LINES {
+            self.lines.drain(..self.lines.len() - MAX_LINES);
+        }
+        let first = self.lines.len().saturating_sub(VISIBLE_LINES);
+        ui.send_message(TextMessage::text(
+            self.output,
+            MessageDirection::ToWidget,
+            self.lines[first..].join("\n"),
+        ));
+    }
+
+    fn sync_input(&self, ui: &Ui) {
+        ui.send_message(TextMessage::text(
+            self.input_text,
+            MessageDirection::ToWidget,
+            format!("> {}_", self.input),
+        ));
+    }
+
+    fn submit(&mut self, ui: &Ui, registry: &mut CommandRegistry) {
+        let line = std::mem::take(&mut self.input);
+        self.print(ui, &format!("> {}", line));
+        if !line.trim().is_empty() {
+            match registry.execute(&line) {
+                Ok(output) if !output.is_empty() => self.print(ui, &output),
+                Ok(_) => (),
+                Err(error) => self.print(ui, &format!("error: {}", error)),
+            }
+            self.history.push(line);
+        }
+        self.history_position = self.history.len();
+    }
+
+    fn recall(&mut self, offset: isize) {
+        let position = self.history_position as isize + offset;
+        if position >= 0 && position as usize <= self.history.len() {
+            self.history_position = position as usize;
+            self.input = self
+                .history
+                .get(self.history_position)
+                .cloned()
+                .unwrap_or_default();
+        }
+    }
+
+    /// Returns true if the event was consumed by the console and must not reach the game.
+    pub fn process_input_event(
+        &mut self,
+        ui: &Ui,
+        registry: &mut CommandRegistry,
+        event: &Event<()>,
+    ) -> bool {
+        let is_input_event = matches!(
+            event,
+            Event::DeviceEvent { .. }
+                | Event::WindowEvent {
+                    event: WindowEvent::KeyboardInput { .. }
+                        | WindowEvent::ReceivedCharacter(_)
+                        | WindowEvent::MouseInput { .. }
+                        | WindowEvent::MouseWheel { .. },
+                    ..
+                }
+        );
+        if !is_input_event {
+            return false;
+        }
+        let was_visible = self.visible;
+        if let Event::WindowEvent { event, .. } = event {
+            match event {
+                WindowEvent::KeyboardInput { input, .. } => {
+                    if input.state != ElementState::Pressed {
+                        return was_visible;
+                    }
+                    match input.virtual_keycode {
+                        Some(VirtualKeyCode::Grave) => {
+                            self.set_visible(ui, !self.visible);
+                            self.sync_input(ui);
+                            return true;
+                        }
+                        _ if !self.visible => (),
+                        Some(VirtualKeyCode::Return) | Some(VirtualKeyCode::NumpadEnter) => {
+                            self.submit(ui, registry)
+                        }
+                        Some(VirtualKeyCode::Back) => {
+                            self.input.pop();
+                        }
+                        Some(VirtualKeyCode::Up) => self.recall(-1),
+                        Some(VirtualKeyCode::Down) => self.recall(1),
+                        Some(VirtualKeyCode::Escape) => self.set_visible(ui, false),
+                        _ => (),
+                    }
+                    if self.visible {
+                        self.sync_input(ui);
+                    }
+                }
+                // Toggle key produces a character too, it must not get into the input.
+                WindowEvent::ReceivedCharacter(c)
+                    if self.visible && !c.is_control() && *c != '`' && *c != '~' =>
+                {
+                    self.input.push(*c);
+                    self.sync_input(ui);
+                }
+                _ => (),
+            }
+        }
+        was_visible
+    }
+}
@@ -0,0 +1,319 @@
+use rg3d::{
+    core::{algebra::Vector3, math::ray::Ray},
+    scene::graph::Graph,
+};
+
+use crate::navmesh::{triangle_bounds, Navmesh};
+
+// Level may contain hand-made cover points - nodes which names start with this prefix,
+// they are used instead of generated ones. Cover point looks toward the obstacle, names
+// containing "Crouch" are crouch covers, the rest are full height.
+pub const COVER_PREFIX: &str = "Cover";
+
+// Heights above the floor which must be blocked by an obstacle for a crouching and standing
+// character.
+const CROUCH_HEIGHT: f32 = 0.4;
+const FULL_HEIGHT: f32 = 0.8;
+
+// How far from the edge of the navmesh an obstacle is looked for.
+const PROBE_DISTANCE: f32 = 1.0;
+
+// Generated cover points are never closer to each other than that.
+const SPACING: f32 = 1.0;
+
+// Cover protects from threats within this angle from its facing direction.
+const PROTECTION_ANGLE: f32 = std::f32::consts::FRAC_PI_3;
+
+// Cover too close to a threat is useless, the enemy simply walks around.
+const MIN_THREAT_DISTANCE: f32 = 3.0;
+
+// Full cover is preferred over crouch cover which is this much closer.
+const FULL_COVER_BONUS: f32 = 2.0;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CoverHeight {
+    Crouch,
+    Full,
+}
+
+pub struct CoverPoint {
+    // Point on the floor where a character stands.
+    pub position: Vector3<f32>,
+    // Horizontal direction toward the obstacle.
+    pub facing: Vector3<f32>,
+    pub height: CoverHeight,
+    // Name of the character which uses the cover.
+    owner: Option<String>,
+}
+
+impl CoverPoint {
+    fn new(position: Vector3<f32>, facing: Vector3<f32>, height: CoverHeight) -> Self {
+        Self {
+            position,
+            facing,
+            height,
+            owner: None,
+        }
+    }
+
+    pub fn protects_from(&self, threat: Vector3<f32>) -> bool {
+        let to_threat = Vector3::new(threat.x - self.position.x, 0.0, threat.z - self.position.z);
+        to_threat.norm() >= MIN_THREAT_DISTANCE
+            && self.facing.dot(&to_threat.normalize()) >= PROTECTION_ANGLE.cos()
+    }
+}
+
+/// Every cover point of a level. Characters claim cover points they go to, so two of them
+/// never hide behind the same one.
+#[derive(Default)]
+pub struct CoverPoints {
+    points: Vec<CoverPoint>,
+}
+
+impl CoverPoints {
+    /// Uses hand-made cover points of a level if there are any, otherwise places them along
+    /// the edges of the navmesh which have static obstacles right behind them.
+    pub fn build(graph: &Graph, navmesh: &Navmesh, obstacles: &[[Vector3<f32>; 3]]) -> Self {
+        let authored = graph
+            .linear_iter()
+            .filter(|node| node.name().starts_with(COVER_PREFIX))
+            .map(|node| {
+                let look = node.look_vector();
+                let height = if node.name().contains("Crouch") {
+                    CoverHeight::Crouch
+                } else {
+                    CoverHeight::Full
+                };
+                CoverPoint::new(
+                    node.global_position(),
+                    Vector3::new(look.x, 0.0, look.z)
+                        .try_normalize(f32::EPSILON)
+                        .unwrap_or_else(Vector3::z),
+                    height,
+                )
+            })
+            .collect::<Vec<_>>();
+        if !authored.is_empty() {
+            return Self { points: authored };
+        }
+        Self {
+            points: generate(navmesh, obstacles),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &CoverPoint> {
+        self.points.iter()
+    }
+
+    pub fn get(&self, index: usize) -> &CoverPoint {
+        &self.points[index]
+    }
+
+    /// Finds the best free cover from `threat` within `radius` from `from` which can be
+    /// reached by walking. Closer covers are better, full height ones are preferred. Returns
+    /// index of the cover with the path to it.
+    pub fn find_best(
+        &self,
+        navmesh: &Navmesh,
+        from: Vector3<f32>,
+        threat: Vector3<f32>,
+        radius: f32,
+        owner: &str,
+    ) -> Option<(usize, Vec<Vector3<f32>>)> {
+        let score = |point: &CoverPoint| {
+            let bonus = match point.height {
+                CoverHeight::Full => FULL_COVER_BONUS,
+                CoverHeight::Crouch => 0.0,
+            };
+            (point.position - from).norm() - bonus
+        };
+        let mut candidates = self
+            .points
+            .iter()
+            .enumerate()
+            .filter(|(_, point)| {
+                point.owner.as_deref().is_none_or(|o| o == owner)
+                    && (point.position - from).norm() <= radius
+                    && point.protects_from(threat)
+            })
+            .collect::<Vec<_>>();
+        candidates.sort_by(|(_, a), (_, b)| score(a).total_cmp(&score(b)));
+        // Path search is the most expensive part, so it is done for the best candidates
+        // first until one of them is reachable.
+        candidates.into_iter().find_map(|(index, point)| {
+            navmesh
+                .find_path(from, point.position)
+                .map(|path| (index, path))
+        })
+    }
+
+    // Returns false if the cover is used by someone else.
+    pub fn claim(&mut self, index: usize, owner: &str) -> bool {
+        if self.points[index]
+            .owner
+            .as_deref()
+            .is_some_and(|o| o != owner)
+        {
+            return false;
+        }
+        self.release(owner);
+        self.points[index].owner = Some(owner.to_owned());
+        true
+    }
+
+    pub fn release(&mut self, owner: &str) {
+        for point in self.points.iter_mut() {
+            if point.owner.as_deref() == Some(owner) {
+                point.owner = None;
+            }
+        }
+    }
+
+    pub fn claimed_by(&self, owner: &str) -> Option<usize> {
+        self.points
+            .iter()
+            .position(|point| point.owner.as_deref() == Some(owner))
+    }
+}
+
+// Every boundary edge of the navmesh is probed with horizontal rays at crouch and full
+// height, an obstacle hit by them makes a cover.
+fn generate(navmesh: &Navmesh, obstacles: &[[Vector3<f32>; 3]]) -> Vec<CoverPoint> {
+    let mut points: Vec<CoverPoint> = Vec::new();
+    for ([a, b], inside) in navmesh.boundary_edges() {
+        let middle = (a + b).scale(0.5);
+        let edge = b - a;
+        let mut outside = Vector3::new(-edge.z, 0.0, edge.x);
+        if outside.dot(&(middle - inside)) < 0.0 {
+            outside = -outside;
+        }
+        let outside = match outside.try_normalize(f32::EPSILON) {
+            Some(outside) => outside,
+            None => continue,
+        };
+        if points
+            .iter()
+            .any(|point| (point.position - middle).norm() < SPACING)
+        {
+            continue;
+        }
+
+        let is_blocked = |height: f32| {
+            let ray = Ray::new(
+                middle + Vector3::new(0.0, height, 0.0),
+                outside.scale(PROBE_DISTANCE),
+            );
+            let (ray_min, ray_max) = (
+                ray.origin.inf(&(ray.origin + ray.dir)),
+                ray.origin.sup(&(ray.origin + ray.dir)),
+            );
+            obstacles.iter().any(|triangle| {
+                let (min, max) = triangle_bounds(triangle);
+                min.x <= ray_max.x
+                    && max.x >= ray_min.x
+                    && min.y <= ray_max.y
+                    && max.y >= ray_min.y
+                    && min.z <= ray_max.z
+                    && max.z >= ray_min.z
+                    && ray.triangle_intersection(triangle).is_some()
+            })
+        };
+        let height = if is_blocked(FULL_HEIGHT) {
+            CoverHeight::Full
+        } else if is_blocked(CROUCH_HEIGHT) {
+            CoverHeight::Crouch
+        } else {
+            continue;
+        };
+        points.push(CoverPoint::new(middle, outside, height));
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Threat is in front of every cover which faces +z.
+    fn threat() -> Vector3<f32> {
+        Vector3::new(0.0, 0.0, 8.0)
+    }
+
+    fn square_floor() -> Navmesh {
+        let vertices = vec![
+            Vector3::new(-10.0, 0.0, -10.0),
+            Vector3::new(10.0, 0.0, -10.0),
+            Vector3::new(10.0, 0.0, 10.0),
+            Vector3::new(-10.0, 0.0, 10.0),
+        ];
+        Navmesh::new(vertices, vec![[0, 1, 3], [1, 2, 3]], 0.5)
+    }
+
+    fn covers() -> CoverPoints {
+        CoverPoints {
+            points: vec![
+                // The closest one, but the threat is behind it.
+                CoverPoint::new(
+                    Vector3::new(0.0, 0.0, 1.0),
+                    -Vector3::z(),
+                    CoverHeight::Full,
+                ),
+                CoverPoint::new(
+                    Vector3::new(2.0, 0.0, 0.0),
+                    Vector3::z(),
+                    CoverHeight::Crouch,
+                ),
+                CoverPoint::new(
+                    Vector3::new(0.0, 0.0, -3.0),
+                    Vector3::z(),
+                    CoverHeight::Full,
+                ),
+            ],
+        }
+    }
+
+    #[test]
+    fn cover_protects_only_from_threats_in_front() {
+        let cover = CoverPoint::new(Vector3::default(), Vector3::z(), CoverHeight::Full);
+        assert!(cover.protects_from(Vector3::new(1.0, 0.0, 10.0)));
+        assert!(!cover.protects_from(Vector3::new(0.0, 0.0, -10.0)));
+        assert!(!cover.protects_from(Vector3::new(10.0, 0.0, 0.0)));
+        // Enemy that close simply walks around the obstacle.
+        assert!(!cover.protects_from(Vector3::new(0.0, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn full_cover_is_preferred_over_slightly_closer_crouch_cover() {
+        let (index, path) = covers()
+            .find_best(&square_floor(), Vector3::default(), threat(), 10.0, "alice")
+            .unwrap();
+        assert_eq!(index, 2);
+        assert!((path.last().unwrap() - Vector3::new(0.0, 0.0, -3.0)).norm() < 0.01);
+        // Nothing protects outside of the radius.
+        assert!(covers()
+            .find_best(&square_floor(), Vector3::default(), threat(), 1.5, "alice")
+            .is_none());
+    }
+
+    #[test]
+    fn cover_claimed_by_another_is_skipped() {
+        let navmesh = square_floor();
+        let mut covers = covers();
+        assert!(covers.claim(2, "alice"));
+        assert!(!covers.claim(2, "bob"));
+        let best = |covers: &CoverPoints, owner| {
+            covers
+                .find_best(&navmesh, Vector3::default(), threat(), 10.0, owner)
+                .map(|(index, _)| index)
+        };
+        assert_eq!(best(&covers, "bob"), Some(1));
+        assert_eq!(best(&covers, "alice"), Some(2));
+        covers.release("alice");
+        assert_eq!(covers.claimed_by("alice"), None);
+        assert_eq!(best(&covers, "bob"), Some(2));
+    }
+}
@@ -0,0 +1,220 @@
+use std::{
+    collections::VecDeque,
+    path::Path,
+    sync::{Arc, RwLock},
+};
+
+use rg3d::{
+    core::{
+        algebra::{Matrix4, UnitQuaternion, Vector3},
+        math::Matrix4Ext,
+        pool::Handle,
+    },
+    engine::resource_manager::ResourceManager,
+    renderer::surface::{SurfaceBuilder, SurfaceSharedData},
+    scene::{
+        base::BaseBuilder,
+        graph::Graph,
+        mesh::{MeshBuilder, RenderPath},
+        node::Node,
+        transform::TransformBuilder,
+    },
+};
+
+// Decal is lifted off the surface a bit, otherwise it flickers through it.
+const SURFACE_OFFSET: f32 = 0.005;
+
+/// Local transform of a decal relative to the node it sticks to.
+#[derive(Debug, PartialEq)]
+pub struct Placement {
+    pub position: Vector3<f32>,
+    pub rotation: UnitQuaternion<f32>,
+    pub scale: Vector3<f32>,
+}
+
+/// Places a square decal of `size` at the point of a surface with `normal`, turned by `roll`
+/// radians around the normal, so the front of the quad (its Z axis) looks out of the
+/// surface. `parent` is global transform of the node the decal is attached to.
+pub fn place(
+    point: Vector3<f32>,
+    normal: Vector3<f32>,
+    size: f32,
+    roll: f32,
+    parent: &Matrix4<f32>,
+) -> Placement {
+    let normal = normal
+        .try_normalize(f32::EPSILON)
+        .unwrap_or_else(Vector3::y);
+    // Any direction that is not parallel to the normal will do as "up".
+    let up = if normal.y.abs() > 0.99 {
+        Vector3::x()
+    } else {
+        Vector3::y()
+    };
+    let rotation = UnitQuaternion::face_towards(&normal, &up)
+        * UnitQuaternion::from_axis_angle(&Vector3::z_axis(), roll);
+
+    let parent_scale = Vector3::new(
+        parent.side().norm(),
+        parent.up().norm(),
+        parent.look().norm(),
+    )
+    .map(|s| s.max(f32::EPSILON));
+    let parent_rotation = UnitQuaternion::from_matrix(&parent.basis());
+    let inverse = parent.try_inverse().unwrap_or_else(Matrix4::identity);
+    Placement {
+        position: inverse
+            .transform_point(&(point + normal.scale(SURFACE_OFFSET)).into())
+            .coords,
+        rotation: parent_rotation.inverse() * rotation,
+        scale: Vector3::new(size, size, size).component_div(&parent_scale),
+    }
+}
+
+/// Every decal in the world. There are never more than the limit of them, when a new one
+/// is placed the oldest ones are removed.
+#[derive(Default)]
+pub struct Decals {
+    decals: VecDeque<Handle<Node>>,
+}
+
+impl Decals {
+    #[allow(clippy::too_many_arguments)]
+    pub fn add(
+        &mut self,
+        graph: &mut Graph,
+        resource_manager: ResourceManager,
+        texture: &str,
+        parent: Handle<Node>,
+        point: Vector3<f32>,
+        normal: Vector3<f32>,
+        size: f32,
+        roll: f32,
+        limit: usize,
+    ) {
+        // Decals of removed nodes are gone together with them.
+        self.decals.retain(|decal| graph.is_valid_handle(*decal));
+        while !self.decals.is_empty() && self.decals.len() >= limit {
+            graph.remove_node(self.decals.pop_front().unwrap());
+        }
+        if limit == 0 {
+            return;
+        }
+
+        let placement = place(point, normal, size, roll, &graph[parent].global_transform());
+        let decal = MeshBuilder::new(
+            BaseBuilder::new().with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(placement.position)
+                    .with_local_rotation(placement.rotation)
+                    .with_local_scale(placement.scale)
+                    .build(),
+            ),
+        )
+        .with_surfaces(vec![SurfaceBuilder::new(Arc::new(RwLock::new(
+            SurfaceSharedData::make_quad(Matrix4::identity()),
+        )))
+        .with_diffuse_texture(resource_manager.request_texture(Path::new(texture)))
+        .build()])
+        .with_cast_shadows(false)
+        // Forward path is needed for transparency.
+        .with_render_path(RenderPath::Forward)
+        .build(graph);
+        graph.link_nodes(decal, parent);
+        self.decals.push_back(decal);
+    }
+
+    pub fn clear(&mut self) {
+        self.decals.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: f32 = 0.25;
+
+    fn close(a: Vector3<f32>, b: Vector3<f32>) -> bool {
+        (a - b).norm() < 1.0e-4
+    }
+
+    // Global transform of a decal placed under the parent.
+    fn global(placement: &Placement, parent: &Matrix4<f32>) -> Matrix4<f32> {
+        parent
+            * Matrix4::new_translation(&placement.position)
+            * placement.rotation.to_homogeneous()
+            * Matrix4::new_nonuniform_scaling(&placement.scale)
+    }
+
+    #[test]
+    fn wall_decal_faces_out_of_wall() {
+        let point = Vector3::new(1.0, 1.5, -2.0);
+        assert_eq!(
+            place(point, Vector3::z(), SIZE, 0.0, &Matrix4::identity()),
+            Placement {
+                position: point + Vector3::z().scale(SURFACE_OFFSET),
+                rotation: UnitQuaternion::identity(),
+                scale: Vector3::new(SIZE, SIZE, SIZE),
+            }
+        );
+    }
+
+    #[test]
+    fn floor_decal_faces_up() {
+        let placement = place(
+            Vector3::default(),
+            Vector3::new(0.0, 2.0, 0.0),
+            SIZE,
+            0.0,
+            &Matrix4::identity(),
+        );
+        assert!(close(placement.rotation * Vector3::z(), Vector3::y()));
+        // Up is taken from X axis, because Y axis is parallel to the normal.
+        assert!(close(placement.rotation * Vector3::x(), Vector3::z()));
+        assert!(close(
+            placement.position,
+            Vector3::y().scale(SURFACE_OFFSET)
+        ));
+    }
+
+    #[test]
+    fn roll_turns_decal_around_normal() {
+        let placement = place(
+            Vector3::default(),
+            Vector3::z(),
+            SIZE,
+            std::f32::consts::FRAC_PI_2,
+            &Matrix4::identity(),
+        );
+        assert!(close(placement.rotation * Vector3::z(), Vector3::z()));
+        assert!(close(placement.rotation * Vector3::x(), Vector3::y()));
+    }
+
+    #[test]
+    fn decal_keeps_global_size_and_direction_under_transformed_parent() {
+        let parent = Matrix4::new_translation(&Vector3::new(1.0, 2.0, 3.0))
+            * UnitQuaternion::from_axis_angle(&Vector3::y_axis(), 1.0).to_homogeneous()
+            * Matrix4::new_scaling(2.0);
+        let point = Vector3::new(2.0, 2.5, 3.0);
+        let normal = Vector3::new(1.0, 1.0, 0.0).normalize();
+        let global = global(&place(point, normal, SIZE, 0.3, &parent), &parent);
+        let axis = |i: usize| global.column(i).xyz();
+        let position = global.column(3).xyz();
+        assert!(close(position, point + normal.scale(SURFACE_OFFSET)));
+        assert!(close(axis(2).normalize(), normal));
+        for i in 0..3 {
+            assert!((axis(i).norm() - SIZE).abs() < 1.0e-4);
+        }
+    }
+
+    #[test]
+    fn zero_normal_is_treated_as_floor() {
+        let point = Vector3::new(0.0, 1.0, 0.0);
+        let parent = Matrix4::identity();
+        assert_eq!(
+            place(point, Vector3::default(), SIZE, 0.5, &parent),
+            place(point, Vector3::y(), SIZE, 0.5, &parent)
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use crate::console::{CvarValue, Cvars};
+
+/// Everything that makes a bot weaker or stronger. Every profile is stored in cvars named
+/// `bot_<profile>_<parameter>`, so profiles can be tuned from config scripts, and the one
+/// used for new bots is chosen by `bot_difficulty`.
+#[derive(Clone, Debug)]
+pub struct Difficulty {
+    // Time in seconds between spotting an enemy and the first shot.
+    pub reaction_time: f32,
+    // Aim error in radians right after spotting an enemy, it goes down while the bot keeps
+    // the enemy in sight.
+    pub aim_error: f32,
+    // Radians per second.
+    pub turn_speed: f32,
+    pub view_distance: f32,
+    // From 0 to 1, aggressive bots retreat later and do not bother with cover.
+    pub aggression: f32,
+}
+
+pub const PROFILES: &[&str] = &["easy", "normal", "hard", "custom"];
+
+// Default values of every profile in the order of `PROFILES`: reaction time, aim error in
+// degrees, turn speed in degrees per second, view distance and aggression.
+const DEFAULTS: [[f32; 5]; 4] = [
+    [0.8, 15.0, 120.0, 15.0, 0.2],
+    [0.4, 4.0, 240.0, 25.0, 0.5],
+    [0.2, 1.5, 480.0, 40.0, 0.8],
+    [0.4, 4.0, 240.0, 25.0, 0.5],
+];
+
+const PARAMETERS: [(&str, &str); 5] = [
+    (
+        "reaction_time",
+        "seconds between spotting an enemy and the first shot",
+    ),
+    (
+        "aim_error",
+        "aim error in degrees right after spotting an enemy",
+    ),
+    ("turn_speed", "turn speed in degrees per second"),
+    ("view_distance", "how far bots can see"),
+    (
+        "aggression",
+        "from 0 to 1, aggressive bots retreat later and skip cover",
+    ),
+];
+
+pub fn register_cvars(cvars: &mut Cvars) {
+    cvars.register(
+        "bot_difficulty",
+        CvarValue::String("normal".to_owned()),
+        "difficulty profile of new bots: easy, normal, hard or custom",
+    );
+    for (profile, defaults) in PROFILES.iter().zip(DEFAULTS.iter()) {
+        for ((parameter, description), default) in PARAMETERS.iter().zip(defaults.iter()) {
+            cvars.register(
+                &format!("bot_{}_{}", profile, parameter),
+                CvarValue::Float(*default),
+                &format!("{}, {} profile", description, profile),
+            );
+        }
+    }
+}
+
+impl Difficulty {
+    pub fn from_cvars(cvars: &Cvars, profile: &str) -> Result<Self, String> {
+        if !PROFILES.contains(&profile) {
+            return Err(format!(
+                "unknown difficulty '{}', expected one of: {}",
+                profile,
+                PROFILES.join(", ")
+            ));
+        }
+        let get = |parameter: &str| cvars.float(&format!("bot_{}_{}", profile, parameter));
+        Ok(Self {
+            reaction_time: get("reaction_time").max(0.0),
+            aim_error: get("aim_error").max(0.0).to_radians(),
+            turn_speed: get("turn_speed").max(1.0).to_radians(),
+            view_distance: get("view_distance").max(0.0),
+            aggression: get("aggression").clamp(0.0, 1.0),
+        })
+    }
+}
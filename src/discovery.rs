@@ -0,0 +1,202 @@
+use std::{
+    io::{self, ErrorKind},
+    net::{Ipv4Addr, SocketAddr, UdpSocket},
+    time::{Duration, Instant},
+};
+
+// Default UDP port which servers listen for discovery queries on.
+pub const DISCOVERY_PORT: u16 = 27016;
+
+// Bumped every time client and server stop being able to talk to each other.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+const QUERY: &[u8] = b"SHOOTER_DISCOVER";
+const RESPONSE_HEADER: &str = "SHOOTER_SERVER";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ServerInfo {
+    pub name: String,
+    pub map: String,
+    pub mode: String,
+    pub players: u32,
+    pub max_players: u32,
+    pub protocol_version: u32,
+}
+
+#[derive(Clone, Debug)]
+pub struct DiscoveredServer {
+    pub address: SocketAddr,
+    pub info: ServerInfo,
+}
+
+impl ServerInfo {
+    fn encode(&self) -> Vec<u8> {
+        // One field per line, so make sure that user-defined strings can't break the layout.
+        let sanitize = |s: &str| s.replace(['\n', '\r'], " ");
+        format!(
+            "{}\n{}\n{}\n{}\n{}\n{}\n{}",
+            RESPONSE_HEADER,
+            self.protocol_version,
+            sanitize(&self.name),
+            sanitize(&self.map),
+            sanitize(&self.mode),
+            self.players,
+            self.max_players
+        )
+        .into_bytes()
+    }
+
+    fn decode(data: &[u8]) -> Option<Self> {
+        let text = std::str::from_utf8(data).ok()?;
+        let mut lines = text.split('\n');
+        if lines.next()? != RESPONSE_HEADER {
+            return None;
+        }
+        Some(Self {
+            protocol_version: lines.next()?.parse().ok()?,
+            name: lines.next()?.to_owned(),
+            map: lines.next()?.to_owned(),
+            mode: lines.next()?.to_owned(),
+            players: lines.next()?.parse().ok()?,
+            max_players: lines.next()?.parse().ok()?,
+        })
+    }
+}
+
+/// Server side of the discovery. Answers every discovery query with current server info,
+/// must be polled regularly (once per frame is fine) because the socket is non-blocking.
+pub struct DiscoveryResponder {
+    socket: UdpSocket,
+}
+
+impl DiscoveryResponder {
+    pub fn bind(port: u16) -> io::Result<Self> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, port))?;
+        socket.set_nonblocking(true)?;
+        Ok(Self { socket })
+    }
+
+    pub fn poll(&self, info: &ServerInfo) -> io::Result<()> {
+        let mut buffer = [0; 64];
+        loop {
+            match self.socket.recv_from(&mut buffer) {
+                Ok((size, sender)) => {
+                    if &buffer[..size] == QUERY {
+                        self.socket.send_to(&info.encode(), sender)?;
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(()),
+                // Ignore errors caused by ICMP "port unreachable" from previous replies.
+                Err(e) if e.kind() == ErrorKind::ConnectionReset => (),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// Broadcasts discovery query over local network and gathers every response that arrives
+/// within given timeout. Servers speaking another protocol version are left out, the client
+/// can't join them anyway.
+pub fn discover(port: u16, timeout: Duration) -> io::Result<Vec<DiscoveredServer>> {
+    discover_at(SocketAddr::from((Ipv4Addr::BROADCAST, port)), timeout)
+}
+
+/// Same as [`discover`], but sends the query to specific address. Use loopback address to
+/// find servers running on the same machine.
+pub fn discover_at(target: SocketAddr, timeout: Duration) -> io::Result<Vec<DiscoveredServer>> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+    socket.set_broadcast(true)?;
+    socket.send_to(QUERY, target)?;
+
+    let deadline = Instant::now() + timeout;
+    let mut servers = Vec::<DiscoveredServer>::new();
+    let mut buffer = [0; 1024];
+    loop {
+        let now = Instant::now();
+        if now >= deadline {
+            break;
+        }
+        socket.set_read_timeout(Some(deadline - now))?;
+        match socket.recv_from(&mut buffer) {
+            Ok((size, address)) => {
+                if let Some(info) = ServerInfo::decode(&buffer[..size])
+                    .filter(|info| info.protocol_version == PROTOCOL_VERSION)
+                {
+                    // Same server could answer twice if it is bound to several interfaces.
+                    if servers.iter().all(|s| s.address != address) {
+                        servers.push(DiscoveredServer { address, info });
+                    }
+                }
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut => break,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(servers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        thread,
+    };
+
+    fn info(protocol_version: u32) -> ServerInfo {
+        ServerInfo {
+            name: "Test server".to_owned(),
+            map: "assets/scenes/level.rgs".to_owned(),
+            mode: "deathmatch".to_owned(),
+            players: 3,
+            max_players: 8,
+            protocol_version,
+        }
+    }
+
+    // Runs a responder on loopback interface while the query is made.
+    fn discover_loopback(info: ServerInfo) -> Vec<DiscoveredServer> {
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        socket.set_nonblocking(true).unwrap();
+        let address = socket.local_addr().unwrap();
+        let responder = DiscoveryResponder { socket };
+        let done = Arc::new(AtomicBool::new(false));
+        let server = {
+            let done = done.clone();
+            thread::spawn(move || {
+                while !done.load(Ordering::SeqCst) {
+                    responder.poll(&info).unwrap();
+                    thread::sleep(Duration::from_millis(1));
+                }
+            })
+        };
+        let servers = discover_at(address, Duration::from_millis(500)).unwrap();
+        done.store(true, Ordering::SeqCst);
+        server.join().unwrap();
+        servers
+    }
+
+    #[test]
+    fn server_info_round_trips() {
+        let servers = discover_loopback(info(PROTOCOL_VERSION));
+        assert_eq!(servers.len(), 1);
+        assert_eq!(servers[0].info, info(PROTOCOL_VERSION));
+    }
+
+    #[test]
+    fn other_protocol_version_is_ignored() {
+        assert!(discover_loopback(info(PROTOCOL_VERSION + 1)).is_empty());
+    }
+
+    #[test]
+    fn line_breaks_in_names_do_not_break_layout() {
+        let mut sent = info(PROTOCOL_VERSION);
+        sent.name = "Two\nlines".to_owned();
+        let received = ServerInfo::decode(&sent.encode()).unwrap();
+        assert_eq!(received.name, "Two lines");
+        assert_eq!(received.max_players, sent.max_players);
+    }
+}
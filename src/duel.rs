@@ -0,0 +1,261 @@
+use std::sync::mpsc;
+
+use rg3d::{
+    core::{
+        algebra::{Vector2, Vector3},
+        pool::Pool,
+        rand::{rngs::StdRng, Rng, SeedableRng},
+    },
+    physics::{dynamics::RigidBodyBuilder, geometry::ColliderBuilder},
+    scene::{base::BaseBuilder, Scene},
+};
+
+use crate::{
+    behavior::BehaviorTree,
+    bot::Bot,
+    commands,
+    console::{CommandRegistry, Cvars},
+    cover::CoverPoints,
+    difficulty::{Difficulty, PROFILES},
+    hitbox::Region,
+    layers,
+    material::Materials,
+    message::Message,
+    navmesh::Navmesh,
+    perception::{Noise, NoiseKind, Target},
+    run_autoexec,
+    weapon::Weapon,
+    weapon_kind::{WeaponKind, WeaponKinds},
+    BEHAVIOR_PATH, WEAPONS_PATH,
+};
+
+const DUELS: usize = 20;
+const DUEL_TIME: f32 = 30.0;
+const TIMESTEP: f32 = 1.0 / 60.0;
+// Close enough for bots of every default profile to see each other.
+const DISTANCE: f32 = 12.0;
+// Bots start looking roughly toward each other, but not exactly.
+const MAX_START_YAW_ERROR: f32 = std::f32::consts::FRAC_PI_4;
+const ARENA_SIZE: f32 = 25.0;
+
+// Every pellet counts as a shot.
+#[derive(Default)]
+struct Stats {
+    shots: u32,
+    hits: u32,
+    headshots: u32,
+    kills: u32,
+    kill_time: f32,
+}
+
+/// Runs duels between two bots of the same difficulty on a flat empty arena without a window
+/// and prints their accuracy, so profiles can be compared with each other. Bots use the same
+/// behavior tree, perception, weapons and cvars (autoexec is executed) as in the game.
+pub fn accuracy_report() {
+    let (sender, receiver) = mpsc::channel();
+    let mut console = CommandRegistry::new(sender.clone());
+    commands::register(&mut console);
+    if let Some(text) = run_autoexec(&mut console).filter(|text| !text.is_empty()) {
+        println!("{}", text);
+    }
+    let weapon_kinds = WeaponKinds::load(WEAPONS_PATH).unwrap_or_else(|e| {
+        println!("{}, bots will use the rifle", e);
+        Default::default()
+    });
+    let behavior = BehaviorTree::load(BEHAVIOR_PATH).unwrap_or_else(|e| {
+        println!("{}, bots will only wander", e);
+        Default::default()
+    });
+    // Fixed seed makes every run start with the same positions.
+    let mut rng = StdRng::seed_from_u64(0);
+
+    for profile in PROFILES {
+        let cvars = console.cvars();
+        let difficulty = Difficulty::from_cvars(cvars, profile).unwrap();
+        let mut stats = Stats::default();
+        for _ in 0..DUELS {
+            duel(
+                cvars,
+                &difficulty,
+                weapon_kinds.first(),
+                &behavior,
+                &sender,
+                &receiver,
+                &mut rng,
+                &mut stats,
+            );
+        }
+        println!(
+            "{:>7}: {:5.1}% accuracy ({}/{} shots hit, {} headshots), {}/{} duels won in {:.1} s \
+             on average",
+            profile,
+            100.0 * stats.hits as f32 / stats.shots.max(1) as f32,
+            stats.hits,
+            stats.shots,
+            stats.headshots,
+            stats.kills,
+            DUELS,
+            stats.kill_time / stats.kills.max(1) as f32
+        );
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn duel(
+    cvars: &Cvars,
+    difficulty: &Difficulty,
+    weapon_kind: &WeaponKind,
+    behavior: &BehaviorTree,
+    sender: &mpsc::Sender<Message>,
+    receiver: &mpsc::Receiver<Message>,
+    rng: &mut StdRng,
+    stats: &mut Stats,
+) {
+    // Shots of the previous duel that was over before they were handled.
+    while receiver.try_recv().is_ok() {}
+
+    let mut scene = Scene::new();
+    let floor = scene
+        .physics
+        .add_body(RigidBodyBuilder::new_static().build());
+    scene.physics.add_collider(
+        ColliderBuilder::cuboid(ARENA_SIZE, 0.1, ARENA_SIZE)
+            .translation(0.0, -0.1, 0.0)
+            .collision_groups(layers::collider(layers::WORLD))
+            .build(),
+        floor,
+    );
+    let navmesh = Navmesh::new(
+        vec![
+            Vector3::new(-ARENA_SIZE, 0.0, -ARENA_SIZE),
+            Vector3::new(-ARENA_SIZE, 0.0, ARENA_SIZE),
+            Vector3::new(ARENA_SIZE, 0.0, ARENA_SIZE),
+            Vector3::new(ARENA_SIZE, 0.0, -ARENA_SIZE),
+        ],
+        vec![[0, 1, 2], [0, 2, 3]],
+        0.3,
+    );
+    let mut covers = CoverPoints::default();
+    // Nothing on the arena is thin enough to shoot through.
+    let materials = Materials::default();
+    let fov = cvars.float("bot_fov").to_radians();
+
+    let mut weapons = Pool::new();
+    let mut bots = Vec::new();
+    // Bots of the same layer cannot shoot each other, so one of them stands in for the player.
+    for (name, x, yaw, layer) in [
+        (
+            "A",
+            -DISTANCE * 0.5,
+            std::f32::consts::FRAC_PI_2,
+            layers::BOT,
+        ),
+        (
+            "B",
+            DISTANCE * 0.5,
+            -std::f32::consts::FRAC_PI_2,
+            layers::PLAYER,
+        ),
+    ] {
+        let position = Vector3::new(x, 0.5, 0.0);
+        let mut bot = Bot::new(
+            &mut scene,
+            name.to_owned(),
+            position,
+            fov,
+            difficulty.clone(),
+            sender.clone(),
+            layer,
+        );
+        bot.yaw = yaw + rng.gen_range(-MAX_START_YAW_ERROR..MAX_START_YAW_ERROR);
+        // There is no model, weapon shoots right from the pivot.
+        let model = BaseBuilder::new().build(&mut scene.graph);
+        scene.graph.link_nodes(model, bot.weapon_pivot);
+        let mut weapon = Weapon::from_model(model, model, weapon_kind.clone());
+        weapon.set_owner(layer);
+        bot.weapon = weapons.spawn(weapon);
+        bots.push(bot);
+    }
+
+    let mut noises = Vec::new();
+    let mut time = 0.0;
+    while time < DUEL_TIME {
+        scene.physics.integration_parameters.dt = TIMESTEP;
+        let targets = bots
+            .iter()
+            .map(|bot| Target {
+                position: bot.position(&scene),
+                collider: bot.collider,
+            })
+            .collect::<Vec<_>>();
+        for (i, bot) in bots.iter_mut().enumerate() {
+            // Everyone but itself is an enemy.
+            bot.perceive(TIMESTEP, &scene, &targets[1 - i..2 - i], &noises);
+            bot.think(
+                behavior,
+                &scene,
+                &navmesh,
+                &mut covers,
+                &weapons[bot.weapon],
+                None,
+            );
+            bot.update(TIMESTEP, &mut scene);
+        }
+        noises.clear();
+
+        while let Ok(message) = receiver.try_recv() {
+            match message {
+                Message::ShootWeapon { weapon } => {
+                    let shooter = bots.iter().position(|bot| bot.weapon == weapon).unwrap();
+                    let weapon = &mut weapons[weapon];
+                    if !weapon.can_shoot() {
+                        continue;
+                    }
+                    weapon.shoot(cvars);
+                    noises.push(Noise {
+                        position: bots[shooter].position(&scene),
+                        radius: cvars.float("g_gunshot_noise_radius"),
+                        kind: NoiseKind::Gunshot,
+                        emitter: bots[shooter].collider,
+                    });
+                    let pellets = weapon.cast_shot(
+                        &scene,
+                        &materials,
+                        cvars.float("weapon_penetration"),
+                        cvars.int("weapon_pellet_seed") as u64,
+                        rng,
+                    );
+                    stats.shots += pellets.len() as u32;
+                    let target = &mut bots[1 - shooter];
+                    for pellet in pellets.iter() {
+                        for hit in pellet.hits.iter() {
+                            let region = match target.hitboxes.region_of(hit.intersection.collider)
+                            {
+                                Some(region) => region,
+                                None => continue,
+                            };
+                            stats.hits += 1;
+                            if region == Region::Head {
+                                stats.headshots += 1;
+                            }
+                            let (damage, _) = pellet.impact(hit, weapon.kind(), cvars);
+                            if target.damage(damage * region.damage_multiplier(cvars)) {
+                                stats.kills += 1;
+                                stats.kill_time += time;
+                                return;
+                            }
+                        }
+                    }
+                }
+                Message::ReloadWeapon { weapon } => weapons[weapon].reload(),
+                _ => (),
+            }
+        }
+
+        for weapon in weapons.iter_mut() {
+            weapon.update(TIMESTEP, &mut scene.graph);
+        }
+        scene.update(Vector2::new(1.0, 1.0), TIMESTEP);
+        time += TIMESTEP;
+    }
+}
@@ -0,0 +1,350 @@
+use std::sync::{Arc, RwLock};
+
+use rg3d::{
+    core::{
+        algebra::{UnitQuaternion, Vector3},
+        color::Color,
+        pool::Handle,
+    },
+    renderer::surface::{SurfaceBuilder, SurfaceSharedData},
+    scene::{
+        base::BaseBuilder,
+        graph::Graph,
+        light::{BaseLightBuilder, PointLightBuilder},
+        mesh::{MeshBuilder, RenderPath},
+        node::Node,
+        particle_system::ParticleSystemBuilder,
+    },
+};
+
+use crate::{particle_effect::ParticleEffects, tracer::Tracer};
+
+// Amount of effects of each kind that can be seen at once, when every one of them is in use
+// the oldest one is taken.
+const TRAILS: usize = 64;
+const PARTICLES: usize = 128;
+const LIGHTS: usize = 16;
+
+// Impacts of a shot closer than that to each other are played by a single particle system.
+const IMPACT_MERGE_RADIUS: f32 = 0.5;
+
+// Light of a muzzle flash is seen for a couple of frames only.
+const FLASH_TIME: f32 = 0.05;
+const FLASH_RADIUS: f32 = 4.0;
+
+struct Effect {
+    node: Handle<Node>,
+    // Effect is hidden and free to use when its time is out.
+    time_left: f32,
+    // Particle effect and its version the node was set up for.
+    effect: Option<(usize, u32)>,
+}
+
+// Tracer of a shot, it is redrawn every frame while its head flies and its color fades.
+#[derive(Default)]
+struct Trail {
+    node: Handle<Node>,
+    time_left: f32,
+    lifetime: f32,
+    origin: Vector3<f32>,
+    direction: Vector3<f32>,
+    length: f32,
+    width: f32,
+    speed: f32,
+    tail: f32,
+    color: Color,
+    fade: bool,
+}
+
+// Impact waiting to be played, positions and normals of impacts merged into it are summed up.
+struct Impact {
+    effect: usize,
+    position: Vector3<f32>,
+    normal: Vector3<f32>,
+    count: f32,
+}
+
+/// Shot trails, particle effects and flashes of light are made very often, so they are never
+/// created while playing. Every node is built when a level is loaded and shown again when it
+/// is needed.
+#[derive(Default)]
+pub struct Effects {
+    trails: Vec<Trail>,
+    particles: Vec<Effect>,
+    lights: Vec<Effect>,
+    impacts: Vec<Impact>,
+    next_trail: usize,
+    next_particles: usize,
+    next_light: usize,
+}
+
+impl Effects {
+    pub fn new(graph: &mut Graph) -> Self {
+        // Unit cylinder that faces toward Z axis is shared by every trail.
+        let shape = Arc::new(RwLock::new(SurfaceSharedData::make_cylinder(
+            6,     // Count of sides
+            1.0,   // Radius
+            1.0,   // Height
+            false, // No caps are needed.
+            // Rotate vertical cylinder around X axis to make it face towards Z axis
+            UnitQuaternion::from_axis_angle(&Vector3::x_axis(), 90.0f32.to_radians())
+                .to_homogeneous(),
+        )));
+        let trails = (0..TRAILS)
+            .map(|_| Trail {
+                node: MeshBuilder::new(BaseBuilder::new().with_visibility(false))
+                    .with_surfaces(vec![SurfaceBuilder::new(shape.clone()).build()])
+                    // Do not cast shadows.
+                    .with_cast_shadows(false)
+                    // Make sure to set Forward render path, otherwise the object won't be
+                    // transparent.
+                    .with_render_path(RenderPath::Forward)
+                    .build(graph),
+                ..Default::default()
+            })
+            .collect();
+        // Particle systems are set up by effects they play.
+        let particles = (0..PARTICLES)
+            .map(|_| Effect {
+                node: ParticleSystemBuilder::new(BaseBuilder::new().with_visibility(false))
+                    .build(graph),
+                time_left: 0.0,
+                effect: None,
+            })
+            .collect();
+        // Flashes are too short to cast shadows.
+        let lights = (0..LIGHTS)
+            .map(|_| Effect {
+                node: PointLightBuilder::new(
+                    BaseLightBuilder::new(BaseBuilder::new().with_visibility(false))
+                        .with_color(Color::from_rgba(255, 190, 110, 255))
+                        .cast_shadows(false),
+                )
+                .with_radius(FLASH_RADIUS)
+                .build(graph),
+                time_left: 0.0,
+                effect: None,
+            })
+            .collect();
+        Self {
+            trails,
+            particles,
+            lights,
+            impacts: Vec::with_capacity(PARTICLES),
+            next_trail: 0,
+            next_particles: 0,
+            next_light: 0,
+        }
+    }
+
+    /// Draws a tracer of a shot from the origin along the direction.
+    pub fn shot_trail(
+        &mut self,
+        graph: &mut Graph,
+        origin: Vector3<f32>,
+        direction: Vector3<f32>,
+        length: f32,
+        tracer: &Tracer,
+        colorblind: bool,
+    ) {
+        let trail = match Self::take(&mut self.trails, &mut self.next_trail) {
+            Some(trail) => trail,
+            None => return,
+        };
+        *trail = Trail {
+            node: trail.node,
+            time_left: tracer.lifetime,
+            lifetime: tracer.lifetime,
+            origin,
+            direction: direction
+                .try_normalize(f32::EPSILON)
+                .unwrap_or_else(Vector3::z),
+            length,
+            width: tracer.width,
+            speed: tracer.speed,
+            tail: tracer.tail,
+            color: tracer.color(colorblind),
+            fade: tracer.fade,
+        };
+        let node = &mut graph[trail.node];
+        node.set_visibility(true);
+        node.local_transform_mut()
+            .set_rotation(UnitQuaternion::face_towards(
+                &trail.direction,
+                &Vector3::y(),
+            ));
+        Self::update_trail(graph, trail);
+    }
+
+    /// Short flash of light of a shot.
+    pub fn flash(&mut self, graph: &mut Graph, position: Vector3<f32>) {
+        let light = match Self::take(&mut self.lights, &mut self.next_light) {
+            Some(light) => light,
+            None => return,
+        };
+        light.time_left = FLASH_TIME;
+        let node = &mut graph[light.node];
+        node.set_visibility(true);
+        node.local_transform_mut().set_position(position);
+    }
+
+    /// Plays the effect from the registry at the point, nothing is played if there is no such
+    /// effect.
+    pub fn spawn(
+        &mut self,
+        graph: &mut Graph,
+        registry: &ParticleEffects,
+        name: &str,
+        position: Vector3<f32>,
+        orientation: UnitQuaternion<f32>,
+    ) {
+        if let Some(effect) = registry.find(name) {
+            self.play(graph, registry, effect, position, orientation);
+        }
+    }
+
+    /// Queues the effect from the registry to be played where a shot hit a surface with the
+    /// normal. Impacts of a shot are played at once by `play_impacts`, so a blast of pellets
+    /// does not take a particle system for every pellet.
+    pub fn add_impact(
+        &mut self,
+        registry: &ParticleEffects,
+        name: &str,
+        position: Vector3<f32>,
+        normal: Vector3<f32>,
+    ) {
+        let effect = match registry.find(name) {
+            Some(effect) => effect,
+            None => return,
+        };
+        match self.impacts.iter_mut().find(|impact| {
+            impact.effect == effect
+                && (impact.position / impact.count).metric_distance(&position) < IMPACT_MERGE_RADIUS
+        }) {
+            Some(impact) => {
+                impact.position += position;
+                impact.normal += normal;
+                impact.count += 1.0;
+            }
+            None => self.impacts.push(Impact {
+                effect,
+                position,
+                normal,
+                count: 1.0,
+            }),
+        }
+    }
+
+    // Plays every queued impact at the average point of impacts merged into it.
+    pub fn play_impacts(&mut self, graph: &mut Graph, registry: &ParticleEffects) {
+        let mut impacts = std::mem::take(&mut self.impacts);
+        for impact in impacts.drain(..) {
+            let normal = impact
+                .normal
+                .try_normalize(f32::EPSILON)
+                .unwrap_or_else(Vector3::y);
+            let orientation = if normal == Vector3::y() {
+                UnitQuaternion::from_axis_angle(&Vector3::y_axis(), 0.0)
+            } else {
+                UnitQuaternion::face_towards(&normal, &Vector3::y())
+            };
+            let position = impact.position / impact.count;
+            self.play(graph, registry, impact.effect, position, orientation);
+        }
+        // Keep the memory for the next shot.
+        self.impacts = impacts;
+    }
+
+    // Particle system is set up from scratch only when it played another effect.
+    fn play(
+        &mut self,
+        graph: &mut Graph,
+        registry: &ParticleEffects,
+        effect: usize,
+        position: Vector3<f32>,
+        orientation: UnitQuaternion<f32>,
+    ) {
+        let key = (effect, registry.generation());
+        // Finished particle system which played the same effect needs no new gradient.
+        let particles = match self
+            .particles
+            .iter_mut()
+            .find(|particles| particles.time_left <= 0.0 && particles.effect == Some(key))
+        {
+            Some(particles) => particles,
+            None => match Self::take(&mut self.particles, &mut self.next_particles) {
+                Some(particles) => particles,
+                None => return,
+            },
+        };
+        let definition = registry.get(effect);
+        particles.time_left = definition.lifetime;
+        let particle_system = graph[particles.node].as_particle_system_mut();
+        particle_system.set_visibility(true);
+        particle_system
+            .local_transform_mut()
+            .set_position(position)
+            .set_rotation(orientation);
+        definition.apply(particle_system);
+        if particles.effect != Some(key) {
+            definition.apply_gradient(particle_system);
+            particles.effect = Some(key);
+        }
+    }
+
+    // Hides effects which are over, so they can be used again.
+    pub fn update(&mut self, graph: &mut Graph, dt: f32) {
+        for trail in self.trails.iter_mut() {
+            if trail.time_left > 0.0 {
+                trail.time_left -= dt;
+                if trail.time_left <= 0.0 {
+                    graph[trail.node].set_visibility(false);
+                } else {
+                    Self::update_trail(graph, trail);
+                }
+            }
+        }
+        for effect in self.particles.iter_mut().chain(self.lights.iter_mut()) {
+            if effect.time_left > 0.0 {
+                effect.time_left -= dt;
+                if effect.time_left <= 0.0 {
+                    graph[effect.node].set_visibility(false);
+                }
+            }
+        }
+    }
+
+    // Moves the head of the tracer and fades it.
+    fn update_trail(graph: &mut Graph, trail: &Trail) {
+        let time = trail.lifetime - trail.time_left;
+        let head = if trail.speed > 0.0 {
+            (trail.speed * time).min(trail.length)
+        } else {
+            trail.length
+        };
+        let start = if trail.tail > 0.0 {
+            (head - trail.tail).max(0.0)
+        } else {
+            0.0
+        };
+        let mut color = trail.color;
+        if trail.fade && trail.lifetime > 0.0 {
+            color.a = (color.a as f32 * trail.time_left / trail.lifetime) as u8;
+        }
+        let mesh = graph[trail.node].as_mesh_mut();
+        mesh.set_color(color);
+        mesh.local_transform_mut()
+            .set_position(trail.origin + trail.direction.scale(start))
+            .set_scale(Vector3::new(trail.width, trail.width, head - start));
+    }
+
+    // Next effect in turn, which is the one that was used the longest time ago.
+    fn take<'a, T>(effects: &'a mut [T], next: &mut usize) -> Option<&'a mut T> {
+        if effects.is_empty() {
+            return None;
+        }
+        let index = *next % effects.len();
+        *next = (index + 1) % effects.len();
+        Some(&mut effects[index])
+    }
+}
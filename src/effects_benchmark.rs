@@ -0,0 +1,92 @@
+use std::time;
+
+use rg3d::{
+    core::{
+        algebra::{UnitQuaternion, Vector2, Vector3},
+        rand::{rngs::StdRng, Rng, SeedableRng},
+    },
+    scene::Scene,
+};
+
+use crate::{
+    allocations, effects::Effects, particle_effect::ParticleEffects, tracer::Tracers, EFFECTS_PATH,
+    TRACERS_PATH,
+};
+
+const FRAMES: usize = 600;
+// Every pooled effect is taken at least once before measuring.
+const WARM_UP_FRAMES: usize = 120;
+const TIMESTEP: f32 = 1.0 / 60.0;
+// Several weapons firing at full-auto at once, each of them shoots every few frames.
+const WEAPONS: usize = 8;
+const FRAMES_PER_SHOT: usize = 6;
+
+/// Fires lots of shots in an empty scene without a window and prints how many heap
+/// allocations and how much time a frame of effects takes.
+pub fn run() {
+    let mut registry = match ParticleEffects::load(EFFECTS_PATH, None) {
+        Ok(registry) => registry,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    let impacts = ["impact_concrete", "impact_metal", "blood"];
+    let tracers = Tracers::load(TRACERS_PATH).unwrap_or_default();
+    let mut scene = Scene::new();
+    let mut effects = Effects::new(&mut scene.graph);
+    let mut rng = StdRng::seed_from_u64(0);
+
+    let (mut total, mut worst) = (0, 0);
+    let mut elapsed = time::Duration::default();
+    for frame in 0..WARM_UP_FRAMES + FRAMES {
+        let allocations = allocations::count();
+        let start = time::Instant::now();
+        for weapon in (0..WEAPONS).filter(|w| (frame + w) % FRAMES_PER_SHOT == 0) {
+            let origin = Vector3::new(rng.gen_range(-5.0..5.0), 1.5, rng.gen_range(-5.0..5.0));
+            let direction = Vector3::new(rng.gen_range(-1.0..1.0), -0.1, 1.0);
+            let length = rng.gen_range(1.0..30.0);
+            effects.spawn(
+                &mut scene.graph,
+                &registry,
+                "muzzle_flash",
+                origin,
+                UnitQuaternion::face_towards(&direction, &Vector3::y()),
+            );
+            effects.spawn(
+                &mut scene.graph,
+                &registry,
+                impacts[weapon % impacts.len()],
+                origin + direction.normalize().scale(length),
+                UnitQuaternion::face_towards(&-direction, &Vector3::y()),
+            );
+            effects.flash(&mut scene.graph, origin);
+            effects.shot_trail(
+                &mut scene.graph,
+                origin,
+                direction,
+                length,
+                tracers.find("m4", "ball"),
+                false,
+            );
+        }
+        registry.update(TIMESTEP);
+        effects.update(&mut scene.graph, TIMESTEP);
+        scene.update(Vector2::new(800.0, 600.0), TIMESTEP);
+        if frame >= WARM_UP_FRAMES {
+            elapsed += start.elapsed();
+            let allocations = allocations::count() - allocations;
+            total += allocations;
+            worst = worst.max(allocations);
+        }
+    }
+    println!(
+        "{} weapons firing, {} frames: {:.1} allocations per frame on average, {} at most, \
+         {:.3} ms per frame",
+        WEAPONS,
+        FRAMES,
+        total as f32 / FRAMES as f32,
+        worst,
+        elapsed.as_secs_f32() * 1000.0 / FRAMES as f32
+    );
+}
@@ -0,0 +1,177 @@
+use std::{str::FromStr, sync::mpsc::Sender};
+
+use rg3d::{
+    core::{
+        algebra::{Isometry3, Translation3, Vector3},
+        math::ray::Ray,
+    },
+    physics::{geometry::Ball, pipeline::QueryPipeline},
+    scene::{physics::RayCastOptions, RigidBodyHandle, Scene},
+};
+
+use crate::{layers, message::Message};
+
+/// How impulse and damage of an explosion go down with distance from its center.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Falloff {
+    // Full strength up to the edge of the blast.
+    None,
+    Linear,
+    // Strong near the center, weak most of the way.
+    Quadratic,
+}
+
+impl Falloff {
+    /// Part of the full strength at given distance, from 0 to 1.
+    pub fn scale(self, distance: f32, radius: f32) -> f32 {
+        let rest = (1.0 - distance / radius.max(f32::EPSILON)).clamp(0.0, 1.0);
+        match self {
+            Falloff::None => {
+                if distance <= radius {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            Falloff::Linear => rest,
+            Falloff::Quadratic => rest * rest,
+        }
+    }
+}
+
+impl FromStr for Falloff {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Falloff::None),
+            "linear" => Ok(Falloff::Linear),
+            "quadratic" => Ok(Falloff::Quadratic),
+            _ => Err(format!(
+                "unknown falloff '{}', expected none, linear or quadratic",
+                s
+            )),
+        }
+    }
+}
+
+/// Pushes every dynamic body touched by a sphere of `radius` around `center` away from it
+/// and sends `Message::DamageBody` for each of them, so the game decides what can be hurt.
+/// Bodies hidden behind something else are not affected. Used by anything that blows up:
+/// barrels, grenades and `explode` command of config scripts.
+pub fn explode(
+    scene: &mut Scene,
+    sender: &Sender<Message>,
+    center: Vector3<f32>,
+    radius: f32,
+    impulse: f32,
+    damage: f32,
+    falloff: Falloff,
+) {
+    // Query pipeline of the engine is private, it only casts rays. Explosions are rare, so
+    // building our own one for every blast is cheap enough.
+    let mut query = QueryPipeline::new();
+    query.update(&scene.physics.bodies, &scene.physics.colliders);
+    let mut bodies = Vec::new();
+    query.intersections_with_shape(
+        &scene.physics.colliders,
+        &Isometry3::from_parts(Translation3::from(center), Default::default()),
+        &Ball::new(radius),
+        layers::query(layers::PROJECTILE, layers::SOLID),
+        |_, collider| {
+            let body = RigidBodyHandle::from(collider.parent());
+            if !bodies.contains(&body) {
+                bodies.push(body);
+            }
+            true
+        },
+    );
+
+    let mut intersections = Vec::new();
+    for body in bodies {
+        let position = match scene.physics.bodies.get(body.into()) {
+            Some(rigid_body) if rigid_body.is_dynamic() => rigid_body.position().translation.vector,
+            _ => continue,
+        };
+
+        // The blast reaches the body only if it is the first thing on the way to it.
+        let offset = position - center;
+        scene.physics.cast_ray(
+            RayCastOptions {
+                ray: Ray::new(center, offset),
+                max_len: offset.norm(),
+                groups: layers::query(layers::PROJECTILE, layers::SOLID),
+                sort_results: true,
+            },
+            &mut intersections,
+        );
+        let is_visible = intersections.first().is_none_or(|i| {
+            scene
+                .physics
+                .colliders
+                .get(i.collider.into())
+                .is_some_and(|collider| RigidBodyHandle::from(collider.parent()) == body)
+        });
+        if !is_visible {
+            continue;
+        }
+
+        let scale = falloff.scale(offset.norm(), radius);
+        let direction = offset
+            .try_normalize(f32::EPSILON)
+            .unwrap_or_else(Vector3::y);
+        scene
+            .physics
+            .bodies
+            .get_mut(body.into())
+            .unwrap()
+            .apply_impulse(direction.scale(impulse * scale), true);
+        if damage * scale > 0.0 {
+            sender
+                .send(Message::DamageBody {
+                    body,
+                    amount: damage * scale,
+                })
+                .unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FALLOFFS: [Falloff; 3] = [Falloff::None, Falloff::Linear, Falloff::Quadratic];
+
+    #[test]
+    fn full_strength_at_center() {
+        for falloff in FALLOFFS {
+            assert_eq!(falloff.scale(0.0, 5.0), 1.0);
+        }
+    }
+
+    #[test]
+    fn strength_goes_down_to_edge() {
+        assert_eq!(Falloff::None.scale(2.5, 5.0), 1.0);
+        assert_eq!(Falloff::Linear.scale(2.5, 5.0), 0.5);
+        assert_eq!(Falloff::Quadratic.scale(2.5, 5.0), 0.25);
+        assert_eq!(Falloff::None.scale(5.0, 5.0), 1.0);
+        assert_eq!(Falloff::Linear.scale(5.0, 5.0), 0.0);
+        assert_eq!(Falloff::Quadratic.scale(5.0, 5.0), 0.0);
+    }
+
+    #[test]
+    fn nothing_past_radius() {
+        for falloff in FALLOFFS {
+            assert_eq!(falloff.scale(7.0, 5.0), 0.0);
+        }
+    }
+
+    #[test]
+    fn zero_radius_only_touches_center() {
+        for falloff in FALLOFFS {
+            assert_eq!(falloff.scale(0.0, 0.0), 1.0);
+            assert_eq!(falloff.scale(0.1, 0.0), 0.0);
+        }
+    }
+}
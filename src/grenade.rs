@@ -0,0 +1,222 @@
+use std::sync::{Arc, RwLock};
+
+use rg3d::{
+    core::{
+        algebra::{Point3, Translation3, Vector3},
+        color::Color,
+        pool::Handle,
+    },
+    physics::{
+        dynamics::{CoefficientCombineRule, RigidBodyBuilder},
+        geometry::{ColliderBuilder, Ray},
+        pipeline::QueryPipeline,
+    },
+    renderer::surface::{SurfaceBuilder, SurfaceSharedData},
+    scene::{base::BaseBuilder, mesh::MeshBuilder, node::Node, RigidBodyHandle, Scene},
+};
+
+use crate::layers;
+
+// Grenade is a small ball.
+const RADIUS: f32 = 0.04;
+const FRICTION: f32 = 0.8;
+// Grenade appears that far in front of the camera, so it does not hit the thrower.
+const THROW_OFFSET: f32 = 0.5;
+// Trajectory preview is not simulated for more physics steps than that.
+const MAX_PREVIEW_STEPS: usize = 600;
+
+struct Grenade {
+    node: Handle<Node>,
+    body: RigidBodyHandle,
+    fuse: f32,
+}
+
+/// How a grenade is thrown, the preview of its trajectory is made of the same parameters as
+/// the grenade itself.
+pub struct Throw {
+    pub position: Vector3<f32>,
+    pub velocity: Vector3<f32>,
+    // Time left before the blast.
+    pub fuse: f32,
+    pub restitution: f32,
+}
+
+impl Throw {
+    /// Grenade flies where the camera looks, together with whoever throws it.
+    pub fn new(
+        camera: Vector3<f32>,
+        look: Vector3<f32>,
+        thrower_velocity: Vector3<f32>,
+        speed: f32,
+        fuse: f32,
+        restitution: f32,
+    ) -> Self {
+        let look = look.try_normalize(f32::EPSILON).unwrap_or_else(Vector3::z);
+        Self {
+            position: camera + look.scale(THROW_OFFSET),
+            velocity: look.scale(speed) + thrower_velocity,
+            fuse,
+            restitution,
+        }
+    }
+}
+
+/// Thrown grenades and the one the player is cooking. Grenade is a physical ball which
+/// bounces off solid things and blows up when its fuse runs out. Fuse burns from the moment
+/// the pin is pulled, so a cooked grenade blows up sooner after it is thrown.
+pub struct Grenades {
+    grenades: Vec<Grenade>,
+    // Time since the player pulled the pin of a grenade still in hand.
+    cooking: Option<f32>,
+    // Every grenade looks the same.
+    shape: Arc<RwLock<SurfaceSharedData>>,
+}
+
+impl Default for Grenades {
+    fn default() -> Self {
+        Self {
+            grenades: Default::default(),
+            cooking: None,
+            shape: Arc::new(RwLock::new(SurfaceSharedData::make_sphere(8, 8, RADIUS))),
+        }
+    }
+}
+
+impl Grenades {
+    // Pulls the pin, unless a grenade is cooked already.
+    pub fn cook(&mut self) {
+        if self.cooking.is_none() {
+            self.cooking = Some(0.0);
+        }
+    }
+
+    pub fn cooked(&self) -> Option<f32> {
+        self.cooking
+    }
+
+    // Grenade leaves the hand, returns how long it was cooked if there was one.
+    pub fn stop_cooking(&mut self) -> Option<f32> {
+        self.cooking.take()
+    }
+
+    pub fn throw(&mut self, scene: &mut Scene, throw: &Throw) {
+        let node = MeshBuilder::new(BaseBuilder::new())
+            .with_surfaces(vec![SurfaceBuilder::new(self.shape.clone())
+                // Olive drab.
+                .with_color(Color::from_rgba(80, 90, 50, 255))
+                .build()])
+            .with_cast_shadows(false)
+            .build(&mut scene.graph);
+        let body = scene.physics.add_body(
+            RigidBodyBuilder::new_dynamic()
+                .position(Translation3::from(throw.position).into())
+                .linvel(throw.velocity.x, throw.velocity.y, throw.velocity.z)
+                // Rolling ball is not simulated by the preview, grenade slides instead.
+                .lock_rotations()
+                .build(),
+        );
+        scene.physics.add_collider(
+            ColliderBuilder::ball(RADIUS)
+                .collision_groups(layers::collider(layers::PROJECTILE))
+                .restitution(throw.restitution)
+                // Bounce and sliding do not depend on the surface, the preview knows nothing
+                // about it.
+                .restitution_combine_rule(CoefficientCombineRule::Max)
+                .friction(FRICTION)
+                .friction_combine_rule(CoefficientCombineRule::Max)
+                .build(),
+            body,
+        );
+        scene.physics_binder.bind(node, body);
+        self.grenades.push(Grenade {
+            node,
+            body,
+            fuse: throw.fuse,
+        });
+    }
+
+    // Burns fuses, returns centers of grenades which blew up.
+    pub fn update(&mut self, scene: &mut Scene, dt: f32) -> Vec<Vector3<f32>> {
+        if let Some(time) = self.cooking.as_mut() {
+            *time += dt;
+        }
+        for grenade in self.grenades.iter_mut() {
+            grenade.fuse -= dt;
+        }
+        let mut centers = Vec::new();
+        self.grenades.retain(|grenade| {
+            if grenade.fuse > 0.0 {
+                return true;
+            }
+            centers.push(
+                scene
+                    .physics
+                    .bodies
+                    .get(grenade.body.into())
+                    .map_or_else(Vector3::default, |body| body.position().translation.vector),
+            );
+            // This also removes the body bound to the node.
+            scene.remove_node(grenade.node);
+            false
+        });
+        centers
+    }
+
+    // Grenades of the previous level are gone together with it.
+    pub fn clear(&mut self) {
+        self.grenades.clear();
+        self.cooking = None;
+    }
+}
+
+/// Points a grenade thrown so passes at every physics step until its fuse runs out. It is
+/// simulated the way the physics moves bodies, with the same gravity and time step, and it
+/// bounces off solid things with the restitution and friction of the grenade.
+pub fn trajectory(scene: &Scene, throw: &Throw, points: &mut Vec<Vector3<f32>>) {
+    points.clear();
+    let dt = scene.physics.integration_parameters.dt;
+    let mut position = throw.position;
+    let mut velocity = throw.velocity;
+    let mut time = 0.0;
+    // Ray casts of the engine update its query pipeline every time, the preview casts a ray
+    // per step, so it updates its own pipeline once.
+    let mut query = QueryPipeline::new();
+    query.update(&scene.physics.bodies, &scene.physics.colliders);
+    points.push(position);
+    while time < throw.fuse && dt > 0.0 && points.len() < MAX_PREVIEW_STEPS {
+        velocity += scene.physics.gravity.scale(dt);
+        let step = velocity.scale(dt);
+        let ray = Ray::new(
+            Point3::from(position),
+            step.try_normalize(f32::EPSILON).unwrap_or_default(),
+        );
+        let hit = query.cast_ray_and_get_normal(
+            &scene.physics.colliders,
+            &ray,
+            step.norm() + RADIUS,
+            true,
+            layers::query(layers::PROJECTILE, layers::SOLID),
+        );
+        match hit {
+            Some((_, hit)) => {
+                let normal = hit.normal.try_normalize(f32::EPSILON).unwrap_or_default();
+                position = ray.point_at(hit.toi).coords + normal.scale(RADIUS);
+                let speed = velocity.dot(&normal);
+                if speed < 0.0 {
+                    // Friction slows the grenade down along the surface as much as the surface
+                    // pushes it away.
+                    let tangent = velocity - normal.scale(speed);
+                    let friction = (-speed * FRICTION).min(tangent.norm());
+                    velocity -= normal.scale((1.0 + throw.restitution) * speed)
+                        + tangent
+                            .try_normalize(f32::EPSILON)
+                            .unwrap_or_default()
+                            .scale(friction);
+                }
+            }
+            None => position += step,
+        }
+        time += dt;
+        points.push(position);
+    }
+}
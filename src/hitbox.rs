@@ -0,0 +1,170 @@
+use std::fmt;
+
+use rg3d::{
+    core::{
+        algebra::{Isometry3, Translation3, UnitQuaternion, Vector3},
+        math::Matrix4Ext,
+        pool::Handle,
+    },
+    physics::{
+        dynamics::RigidBodyBuilder,
+        geometry::{ColliderBuilder, InteractionGroups},
+    },
+    scene::{
+        base::BaseBuilder, node::Node, transform::TransformBuilder, ColliderHandle,
+        RigidBodyHandle, Scene,
+    },
+};
+
+use crate::{
+    console::{CvarValue, Cvars},
+    layers,
+};
+
+/// Part of a character's body, hits to different parts deal different damage.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Region {
+    Head,
+    Torso,
+    Arm,
+    Leg,
+}
+
+// Names of regions in cvars with their default damage multipliers.
+const REGIONS: &[(&str, Region, f32)] = &[
+    ("head", Region::Head, 2.0),
+    ("torso", Region::Torso, 1.0),
+    ("arm", Region::Arm, 0.6),
+    ("leg", Region::Leg, 0.7),
+];
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, ..) = REGIONS.iter().find(|(_, r, _)| r == self).unwrap();
+        write!(f, "{}", name)
+    }
+}
+
+impl Region {
+    pub fn damage_multiplier(self, cvars: &Cvars) -> f32 {
+        cvars.float(&format!("g_damage_{}", self)).max(0.0)
+    }
+}
+
+pub fn register_cvars(cvars: &mut Cvars) {
+    for (name, _, multiplier) in REGIONS {
+        cvars.register(
+            &format!("g_damage_{}", name),
+            CvarValue::Float(*multiplier),
+            &format!("damage multiplier of hits to the {}", name),
+        );
+    }
+}
+
+// Bones of a humanoid character which get hitboxes: bone name, region, half extents of the
+// box and position of the bone relative to the character's center, which is used when the
+// model has no such bone. Sizes fit the capsule every character has now.
+const BONES: &[(&str, Region, [f32; 3], [f32; 3])] = &[
+    ("Head", Region::Head, [0.09, 0.09, 0.09], [0.0, 0.35, 0.0]),
+    ("Spine", Region::Torso, [0.15, 0.17, 0.1], [0.0, 0.1, 0.0]),
+    ("LeftArm", Region::Arm, [0.05, 0.17, 0.05], [0.2, 0.1, 0.0]),
+    (
+        "RightArm",
+        Region::Arm,
+        [0.05, 0.17, 0.05],
+        [-0.2, 0.1, 0.0],
+    ),
+    (
+        "LeftUpLeg",
+        Region::Leg,
+        [0.06, 0.18, 0.06],
+        [0.08, -0.27, 0.0],
+    ),
+    (
+        "RightUpLeg",
+        Region::Leg,
+        [0.06, 0.18, 0.06],
+        [-0.08, -0.27, 0.0],
+    ),
+];
+
+struct Hitbox {
+    bone: Handle<Node>,
+    body: RigidBodyHandle,
+    collider: ColliderHandle,
+    region: Region,
+}
+
+/// Boxes attached to the bones of a character which bullets hit instead of its capsule. They
+/// are kinematic bodies following the animated pose, and they never push anything.
+pub struct Hitboxes {
+    hitboxes: Vec<Hitbox>,
+}
+
+impl Hitboxes {
+    /// Looks for the bones under `root` by name. Characters have no skinned models yet, so
+    /// missing bones are added as children of the root at their usual places. Hitboxes are
+    /// never hit by queries made from `owner` layer, so a character cannot shoot itself.
+    pub fn attach(scene: &mut Scene, root: Handle<Node>, owner: u16) -> Self {
+        let mut hitboxes = Vec::new();
+        for (name, region, [x, y, z], [bx, by, bz]) in BONES {
+            let mut bone = scene.graph.find_by_name(root, name);
+            if bone.is_none() {
+                bone = BaseBuilder::new()
+                    .with_name(*name)
+                    .with_local_transform(
+                        TransformBuilder::new()
+                            .with_local_position(Vector3::new(*bx, *by, *bz))
+                            .build(),
+                    )
+                    .build(&mut scene.graph);
+                scene.graph.link_nodes(bone, root);
+            }
+            let body = scene
+                .physics
+                .add_body(RigidBodyBuilder::new_kinematic().build());
+            let collider = scene.physics.add_collider(
+                ColliderBuilder::cuboid(*x, *y, *z)
+                    .collision_groups(layers::hitbox(owner))
+                    .solver_groups(InteractionGroups::none())
+                    .build(),
+                body,
+            );
+            hitboxes.push(Hitbox {
+                bone,
+                body,
+                collider,
+                region: *region,
+            });
+        }
+        Self { hitboxes }
+    }
+
+    pub fn region_of(&self, collider: ColliderHandle) -> Option<Region> {
+        self.hitboxes
+            .iter()
+            .find(|hitbox| hitbox.collider == collider)
+            .map(|hitbox| hitbox.region)
+    }
+
+    // Moves every hitbox to the pose of its bone, it is there after next physics step.
+    pub fn update(&self, scene: &mut Scene) {
+        for hitbox in self.hitboxes.iter() {
+            let transform = scene.graph[hitbox.bone].global_transform();
+            let position = Isometry3::from_parts(
+                Translation3::from(transform.position()),
+                UnitQuaternion::from_matrix(&transform.basis()),
+            );
+            if let Some(body) = scene.physics.bodies.get_mut(hitbox.body.into()) {
+                body.set_next_kinematic_position(position);
+            }
+        }
+    }
+
+    // Bodies of hitboxes are not bound to bones, so they are not removed with the model.
+    pub fn remove(&mut self, scene: &mut Scene) {
+        for hitbox in self.hitboxes.drain(..) {
+            scene.physics.remove_body(hitbox.body);
+        }
+    }
+}
@@ -0,0 +1,333 @@
+use rg3d::{
+    core::{algebra::Vector2, color::Color, pool::Handle},
+    gui::{
+        border::BorderBuilder,
+        brush::Brush,
+        canvas::CanvasBuilder,
+        grid::GridBuilder,
+        message::{MessageDirection, TextMessage, WidgetMessage},
+        text::TextBuilder,
+        widget::WidgetBuilder,
+        HorizontalAlignment, Thickness, VerticalAlignment,
+    },
+};
+
+use crate::{Ui, UiNode};
+
+// How long hit marker stays on the screen.
+const HIT_MARKER_TIME: f32 = 0.25;
+
+// Flight of a cooked grenade is shown by that many dots.
+const TRAJECTORY_DOTS: usize = 24;
+
+// Sizes of HUD elements as fractions of the frame height, so HUD looks the same on every
+// resolution.
+const CROSSHAIR_LENGTH: f32 = 0.012;
+const CROSSHAIR_THICKNESS: f32 = 0.002;
+const CROSSHAIR_MIN_GAP: f32 = 0.006;
+const HIT_MARKER_SIZE: f32 = 0.008;
+const TRAJECTORY_DOT_SIZE: f32 = 0.004;
+const MARGIN: f32 = 0.02;
+
+/// Everything HUD shows, collected by the game every frame.
+pub struct HudState {
+    // Current spread angle of the weapon in radians.
+    pub spread: f32,
+    // Vertical field of view of the camera in radians, needed to project spread on the screen.
+    pub fov: f32,
+    pub health: f32,
+    pub armor: f32,
+    pub ammo: u32,
+    pub reserve_ammo: u32,
+    pub reloading: bool,
+    // Time left before a cooked grenade blows up, if the player holds one.
+    pub grenade_fuse: Option<f32>,
+    // Points on the screen a cooked grenade would fly through, empty if there is none.
+    pub trajectory: Vec<Vector2<f32>>,
+}
+
+pub struct Hud {
+    root: Handle<UiNode>,
+    // Top, bottom, left and right arms of the crosshair.
+    crosshair: [Handle<UiNode>; 4],
+    // Four diagonal dots around the center.
+    hit_marker: [Handle<UiNode>; 4],
+    trajectory: Vec<Handle<UiNode>>,
+    // Amount of trajectory dots shown now.
+    trajectory_dots: usize,
+    health: Handle<UiNode>,
+    ammo: Handle<UiNode>,
+    frame_size: Vector2<f32>,
+    hit_marker_timer: f32,
+    last_health_text: String,
+    last_ammo_text: String,
+}
+
+fn make_rect(ui: &mut Ui, color: Color) -> Handle<UiNode> {
+    BorderBuilder::new(WidgetBuilder::new().with_background(Brush::Solid(color)))
+        .with_stroke_thickness(Thickness::zero())
+        .build(&mut ui.build_ctx())
+}
+
+impl Hud {
+    pub fn new(ui: &mut Ui) -> Self {
+        let crosshair_color = Color::from_rgba(0, 255, 0, 200);
+        let crosshair = [
+            make_rect(ui, crosshair_color),
+            make_rect(ui, crosshair_color),
+            make_rect(ui, crosshair_color),
+            make_rect(ui, crosshair_color),
+        ];
+        let hit_marker = [
+            make_rect(ui, Color::WHITE),
+            make_rect(ui, Color::WHITE),
+            make_rect(ui, Color::WHITE),
+            make_rect(ui, Color::WHITE),
+        ];
+        let trajectory = (0..TRAJECTORY_DOTS)
+            .map(|_| make_rect(ui, Color::from_rgba(255, 220, 80, 200)))
+            .collect::<Vec<_>>();
+        let ctx = &mut ui.build_ctx();
+        let canvas = CanvasBuilder::new(
+            WidgetBuilder::new()
+                .with_hit_test_visibility(false)
+                .with_children(
+                    crosshair
+                        .iter()
+                        .chain(hit_marker.iter())
+                        .chain(trajectory.iter()),
+                ),
+        )
+        .build(ctx);
+        let health = TextBuilder::new(
+            WidgetBuilder::new()
+                .with_horizontal_alignment(HorizontalAlignment::Left)
+                .with_vertical_alignment(VerticalAlignment::Bottom),
+        )
+        .build(ctx);
+        let ammo = TextBuilder::new(
+            WidgetBuilder::new()
+                .with_horizontal_alignment(HorizontalAlignment::Right)
+                .with_vertical_alignment(VerticalAlignment::Bottom),
+        )
+        .build(ctx);
+        let root = GridBuilder::new(
+            WidgetBuilder::new()
+                .with_hit_test_visibility(false)
+                .with_child(canvas)
+                .with_child(health)
+                .with_child(ammo),
+        )
+        .build(ctx);
+        let hud = Self {
+            root,
+            crosshair,
+            hit_marker,
+            trajectory,
+            trajectory_dots: 0,
+            health,
+            ammo,
+            frame_size: Default::default(),
+            hit_marker_timer: 0.0,
+            last_health_text: Default::default(),
+            last_ammo_text: Default::default(),
+        };
+        hud.set_hit_marker_visible(ui, false);
+        for dot in hud.trajectory.iter() {
+            ui.send_message(WidgetMessage::visibility(
+                *dot,
+                MessageDirection::ToWidget,
+                false,
+            ));
+        }
+        hud
+    }
+
+    pub fn set_visible(&self, ui: &Ui, visible: bool) {
+        ui.send_message(WidgetMessage::visibility(
+            self.root,
+            MessageDirection::ToWidget,
+            visible,
+        ));
+    }
+
+    pub fn resize(&mut self, ui: &Ui, width: f32, height: f32) {
+        self.frame_size = Vector2::new(width, height);
+        ui.send_message(WidgetMessage::width(
+            self.root,
+            MessageDirection::ToWidget,
+            width,
+        ));
+        ui.send_message(WidgetMessage::height(
+            self.root,
+            MessageDirection::ToWidget,
+            height,
+        ));
+        let margin = Thickness::uniform(height * MARGIN);
+        for text in [self.health, self.ammo].iter() {
+            ui.send_message(WidgetMessage::margin(
+                *text,
+                MessageDirection::ToWidget,
+                margin,
+            ));
+        }
+
+        // Hit marker does not move, so it is enough to place it once per resize.
+        let size = height * HIT_MARKER_SIZE;
+        let center = self.frame_size.scale(0.5);
+        let offsets = [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)];
+        for (dot, (x, y)) in self.hit_marker.iter().zip(offsets.iter()) {
+            place(
+                ui,
+                *dot,
+                center + Vector2::new(x * size, y * size),
+                Vector2::new(size * 0.5, size * 0.5),
+            );
+        }
+    }
+
+    // Hit marker is white for a hit, yellow for a headshot and red for a kill.
+    pub fn show_hit_marker(&mut self, ui: &Ui, kill: bool, headshot: bool) {
+        self.hit_marker_timer = HIT_MARKER_TIME;
+        let color = if kill {
+            Color::RED
+        } else if headshot {
+            Color::from_rgba(255, 200, 0, 255)
+        } else {
+            Color::WHITE
+        };
+        for dot in self.hit_marker.iter() {
+            ui.send_message(WidgetMessage::background(
+                *dot,
+                MessageDirection::ToWidget,
+                Brush::Solid(color),
+            ));
+        }
+        self.set_hit_marker_visible(ui, true);
+    }
+
+    fn set_hit_marker_visible(&self, ui: &Ui, visible: bool) {
+        for dot in self.hit_marker.iter() {
+            ui.send_message(WidgetMessage::visibility(
+                *dot,
+                MessageDirection::ToWidget,
+                visible,
+            ));
+        }
+    }
+
+    pub fn update(&mut self, ui: &Ui, state: &HudState, dt: f32) {
+        if self.hit_marker_timer > 0.0 {
+            self.hit_marker_timer -= dt;
+            if self.hit_marker_timer <= 0.0 {
+                self.set_hit_marker_visible(ui, false);
+            }
+        }
+
+        // Project spread cone on the screen, so the crosshair shows where bullets may land.
+        let height = self.frame_size.y;
+        let spread_gap = state.spread.tan() / (state.fov * 0.5).tan() * height * 0.5;
+        let gap = height * CROSSHAIR_MIN_GAP + spread_gap;
+        let length = height * CROSSHAIR_LENGTH;
+        let thickness = (height * CROSSHAIR_THICKNESS).max(1.0);
+        let center = self.frame_size.scale(0.5);
+        let vertical = Vector2::new(thickness, length);
+        let horizontal = Vector2::new(length, thickness);
+        place(
+            ui,
+            self.crosshair[0],
+            center - Vector2::new(0.0, gap + length * 0.5),
+            vertical,
+        );
+        place(
+            ui,
+            self.crosshair[1],
+            center + Vector2::new(0.0, gap + length * 0.5),
+            vertical,
+        );
+        place(
+            ui,
+            self.crosshair[2],
+            center - Vector2::new(gap + length * 0.5, 0.0),
+            horizontal,
+        );
+        place(
+            ui,
+            self.crosshair[3],
+            center + Vector2::new(gap + length * 0.5, 0.0),
+            horizontal,
+        );
+
+        let health_text = format!(
+            "Health: {:.0}  Armor: {:.0}",
+            state.health.ceil(),
+            state.armor.ceil()
+        );
+        if health_text != self.last_health_text {
+            ui.send_message(TextMessage::text(
+                self.health,
+                MessageDirection::ToWidget,
+                health_text.clone(),
+            ));
+            self.last_health_text = health_text;
+        }
+
+        // Dots are spread evenly along the flight, the last one is where the grenade blows up.
+        let dots = state.trajectory.len().min(TRAJECTORY_DOTS);
+        let size = (height * TRAJECTORY_DOT_SIZE).max(1.0);
+        for (i, dot) in self.trajectory.iter().enumerate() {
+            if i < dots {
+                let point = if dots > 1 {
+                    i * (state.trajectory.len() - 1) / (dots - 1)
+                } else {
+                    0
+                };
+                place(ui, *dot, state.trajectory[point], Vector2::new(size, size));
+            }
+            if (i < dots) != (i < self.trajectory_dots) {
+                ui.send_message(WidgetMessage::visibility(
+                    *dot,
+                    MessageDirection::ToWidget,
+                    i < dots,
+                ));
+            }
+        }
+        self.trajectory_dots = dots;
+
+        let mut ammo_text = if state.reloading {
+            format!("Reloading...  / {}", state.reserve_ammo)
+        } else {
+            format!("Ammo: {} / {}", state.ammo, state.reserve_ammo)
+        };
+        if let Some(fuse) = state.grenade_fuse {
+            ammo_text = format!("Grenade: {:.1} s  {}", fuse, ammo_text);
+        }
+        if ammo_text != self.last_ammo_text {
+            ui.send_message(TextMessage::text(
+                self.ammo,
+                MessageDirection::ToWidget,
+                ammo_text.clone(),
+            ));
+            self.last_ammo_text = ammo_text;
+        }
+    }
+}
+
+// Places a rectangle of given size centered at given point of a canvas.
+fn place(ui: &Ui, node: Handle<UiNode>, center: Vector2<f32>, size: Vector2<f32>) {
+    ui.send_message(WidgetMessage::desired_position(
+        node,
+        MessageDirection::ToWidget,
+        center - size.scale(0.5),
+    ));
+    ui.send_message(WidgetMessage::width(
+        node,
+        MessageDirection::ToWidget,
+        size.x,
+    ));
+    ui.send_message(WidgetMessage::height(
+        node,
+        MessageDirection::ToWidget,
+        size.y,
+    ));
+}
@@ -0,0 +1,58 @@
+use rg3d::physics::geometry::InteractionGroups;
+
+// Every collision layer of the game. A collider is put on a single layer when it is created,
+// queries choose the layers they hit, so nothing has to be filtered out afterwards. Colliders
+// made in the editor are on every layer at once.
+pub const WORLD: u16 = 1;
+pub const PLAYER: u16 = 1 << 1;
+pub const BOT: u16 = 1 << 2;
+// Movable objects like barrels.
+pub const PROP: u16 = 1 << 3;
+// Volumes that only detect characters, they never block anything else.
+pub const TRIGGER: u16 = 1 << 4;
+pub const PROJECTILE: u16 = 1 << 5;
+// Damage volumes of characters, separate from the capsules they move with.
+pub const HITBOX: u16 = 1 << 6;
+// Small things like shell casings, they only land on the world and props.
+pub const DEBRIS: u16 = 1 << 7;
+
+// Names of the layers in data files.
+pub const NAMES: &[(&str, u16)] = &[
+    ("world", WORLD),
+    ("player", PLAYER),
+    ("bot", BOT),
+    ("prop", PROP),
+    ("trigger", TRIGGER),
+    ("projectile", PROJECTILE),
+    ("hitbox", HITBOX),
+    ("debris", DEBRIS),
+];
+
+// Layers bullets hit by default. Characters are hit by their hitboxes, not by the capsules
+// they move with.
+pub const SHOOTABLE: u16 = WORLD | PROP | HITBOX;
+
+// Layers which stop explosions.
+pub const SOLID: u16 = WORLD | PLAYER | BOT | PROP;
+
+/// Collision groups of a new collider on the layer.
+pub fn collider(layer: u16) -> InteractionGroups {
+    let mask = match layer {
+        TRIGGER => PLAYER | BOT,
+        DEBRIS => WORLD | PROP,
+        // Grenades bounce off solid things, they fly through hitboxes and triggers.
+        PROJECTILE => SOLID,
+        _ => u16::MAX,
+    };
+    InteractionGroups::new(layer, mask)
+}
+
+/// Collision groups of a hitbox which is never hit by queries made from its owner's layer.
+pub fn hitbox(owner: u16) -> InteractionGroups {
+    InteractionGroups::new(HITBOX, !owner)
+}
+
+/// Collision groups of a query made from `source` layer which hits only given layers.
+pub fn query(source: u16, hits: u16) -> InteractionGroups {
+    InteractionGroups::new(source, hits)
+}
@@ -1,277 +1,1324 @@
+#[cfg(feature = "effects-benchmark")]
+mod allocations;
+mod barrel;
+mod behavior;
+mod bot;
+mod commands;
+mod console;
+mod console_ui;
+mod cover;
+mod decal;
+mod difficulty;
+mod discovery;
+mod duel;
+mod effects;
+#[cfg(feature = "effects-benchmark")]
+mod effects_benchmark;
+mod explosion;
+mod grenade;
+mod hitbox;
+mod hud;
+mod layers;
+mod material;
+mod menu;
 mod message;
+mod navmesh;
+mod particle_effect;
+mod perception;
 mod player;
+mod rcon;
+mod shell;
+mod state;
+mod tracer;
 mod weapon;
+mod weapon_kind;
 
 use rg3d::{
     core::{
-        algebra::{UnitQuaternion, Vector3},
-        color::Color,
-        color_gradient::{ColorGradient, GradientPoint},
+        algebra::{UnitQuaternion, Vector2, Vector3},
         math::ray::Ray,
-        numeric_range::NumericRange,
         pool::{Handle, Pool},
+        rand::{self, rngs::StdRng, Rng, SeedableRng},
+        visitor::{Visit, Visitor},
     },
     engine::{resource_manager::ResourceManager, Engine},
-    event::{Event, VirtualKeyCode, WindowEvent},
+    event::{ElementState, Event, VirtualKeyCode, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
-    gui::node::StubNode,
-    renderer::surface::{SurfaceBuilder, SurfaceSharedData},
+    gui::{
+        message::UiMessage as GenericUiMessage,
+        node::{StubNode, UINode},
+        UserInterface,
+    },
     scene::{
-        base::BaseBuilder,
-        graph::Graph,
-        mesh::{MeshBuilder, RenderPath},
-        node::Node,
-        particle_system::{BaseEmitterBuilder, ParticleSystemBuilder, SphereEmitterBuilder},
-        physics::RayCastOptions,
-        transform::TransformBuilder,
-        Scene,
+        physics::{Intersection, RayCastOptions},
+        RigidBodyHandle, Scene,
     },
+    utils::translate_event,
     window::WindowBuilder,
 };
 use std::{
     path::Path,
-    sync::{
-        mpsc::{self, Receiver, Sender},
-        Arc, RwLock,
-    },
-    time,
+    sync::mpsc::{self, Receiver, Sender},
+    time::{self, Duration},
 };
 
+use barrel::{Barrels, CHAIN_DELAY};
+use behavior::BehaviorTree;
+use bot::Bot;
+use console::CommandRegistry;
+use console_ui::ConsoleUi;
+use cover::{CoverHeight, CoverPoints};
+use decal::Decals;
+use difficulty::Difficulty;
+use discovery::{DiscoveryResponder, ServerInfo, DISCOVERY_PORT, PROTOCOL_VERSION};
+use effects::Effects;
+use explosion::Falloff;
+use grenade::{Grenades, Throw};
+use hitbox::Region;
+use hud::{Hud, HudState};
+use material::Materials;
+use menu::{Menu, MenuAction};
 use message::Message;
+use navmesh::Navmesh;
+use particle_effect::ParticleEffects;
+use perception::{Noise, NoiseKind, Target};
 use player::Player;
+use rcon::{Rcon, RCON_PORT};
+use shell::Shells;
+use state::{GameState, StateEvent};
+use tracer::Tracers;
 use weapon::Weapon;
+use weapon_kind::{WeaponKind, WeaponKinds};
 
 // Create our own engine type aliases. These specializations are needed, because the engine
 // provides a way to extend UI with custom nodes and messages.
 type GameEngine = Engine<(), StubNode>;
+type Ui = UserInterface<(), StubNode>;
+type UiNode = UINode<(), StubNode>;
+type UiMessage = GenericUiMessage<(), StubNode>;
+
+// Decision making of bots.
+const BEHAVIOR_PATH: &str = "assets/ai/bot.bt";
+
+// Left on the ground by explosions.
+const SCORCH_TEXTURE: &str = "assets/textures/decals/scorch.png";
+
+// Physical materials of surfaces.
+const MATERIALS_PATH: &str = "assets/materials.txt";
+
+// Particle effects, one per file.
+const EFFECTS_PATH: &str = "assets/effects";
+
+// How shots of weapons are drawn.
+const TRACERS_PATH: &str = "assets/tracers.txt";
 
-// Our game logic will be updated at 60 Hz rate.
-const TIMESTEP: f32 = 1.0 / 60.0;
+// Kinds of weapons the player can have.
+const WEAPONS_PATH: &str = "assets/weapons.txt";
+
+// Config script which is executed on startup, if it exists.
+const AUTOEXEC_PATH: &str = "config/autoexec.cfg";
+
+// Command line options.
+#[derive(Default)]
+struct Options {
+    list_servers: bool,
+    // `--navmesh-report <map>` builds navmesh of the map without a window and tests it.
+    navmesh_report: Option<String>,
+    // `--bot-accuracy-report` runs bot duels of every difficulty without a window.
+    bot_accuracy_report: bool,
+    // `--effects-benchmark` measures allocations made by shot effects without a window, the
+    // game must be built with `effects-benchmark` feature for that.
+    #[cfg(feature = "effects-benchmark")]
+    effects_benchmark: bool,
+    // `--host <name>` makes the game visible to LAN discovery.
+    host: Option<String>,
+    // `--rcon-password <password>` enables remote console while hosting.
+    rcon_password: Option<String>,
+}
+
+impl Options {
+    fn parse() -> Self {
+        let mut options = Options::default();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--list-servers" => options.list_servers = true,
+                "--navmesh-report" => options.navmesh_report = args.next(),
+                "--bot-accuracy-report" => options.bot_accuracy_report = true,
+                #[cfg(feature = "effects-benchmark")]
+                "--effects-benchmark" => options.effects_benchmark = true,
+                "--host" => options.host = Some(args.next().unwrap_or_default()),
+                "--rcon-password" => options.rcon_password = args.next(),
+                _ => println!("Unknown argument {}", arg),
+            }
+        }
+        options
+    }
+}
 
 struct Game {
+    state: GameState,
     scene: Handle<Scene>,
+    map: String,
+    // Map to load when the game enters loading state, current one is reloaded if not set.
+    next_map: Option<String>,
     player: Player,
     weapons: Pool<Weapon>,
+    navmesh: Navmesh,
+    covers: CoverPoints,
+    barrels: Barrels,
+    effects: Effects,
+    bots: Pool<Bot>,
+    next_bot_id: u32,
+    // Noises made since the previous update, bots hear them on next update.
+    noises: Vec<Noise>,
+    behavior: BehaviorTree,
+    materials: Materials,
+    particle_effects: ParticleEffects,
+    tracers: Tracers,
+    weapon_kinds: WeaponKinds,
+    decals: Decals,
+    shells: Shells,
+    grenades: Grenades,
     receiver: Receiver<Message>,
     sender: Sender<Message>,
+    console: CommandRegistry,
+    console_ui: ConsoleUi,
+    hud: Hud,
+    menu: Menu,
+    quit_requested: bool,
+    focused: bool,
+    cursor_grabbed: bool,
+    discovery: Option<DiscoveryResponder>,
+    rcon: Option<Rcon>,
 }
 
 impl Game {
-    pub async fn new(engine: &mut GameEngine) -> Self {
-        let mut scene = Scene::new();
+    pub async fn new(engine: &mut GameEngine, options: Options) -> Self {
         engine
             .resource_manager
             .state()
             .set_textures_path("assets/textures");
+        let (sender, receiver) = mpsc::channel();
+        let map = "scene".to_owned();
+        let weapon_kinds = WeaponKinds::load(WEAPONS_PATH).unwrap_or_else(|e| {
+            println!(
+                "Unable to load weapons, there will be only the rifle. Reason: {}",
+                e
+            );
+            Default::default()
+        });
+        let (scene, player, weapons, navmesh, covers, barrels, effects) =
+            Self::load_level(engine, &map, sender.clone(), weapon_kinds.first())
+                .await
+                .unwrap();
+
+        let mut console = CommandRegistry::new(sender.clone());
+        commands::register(&mut console);
+        let mut hud = Hud::new(&mut engine.user_interface);
+        let (width, height) = engine.renderer.get_frame_size();
+        hud.resize(&engine.user_interface, width as f32, height as f32);
+        let menu = Menu::new(&mut engine.user_interface);
+        menu.resize(&engine.user_interface, width as f32, height as f32);
+        // Console is created after HUD, so it is drawn on top of it.
+        let mut console_ui = ConsoleUi::new(&mut engine.user_interface);
+        if let Some(text) = run_autoexec(&mut console) {
+            console_ui.print(&engine.user_interface, &text);
+        }
+
+        let behavior = BehaviorTree::load(BEHAVIOR_PATH).unwrap_or_else(|e| {
+            println!(
+                "Unable to load bot behavior, bots will only wander. Reason: {}",
+                e
+            );
+            Default::default()
+        });
+
+        let materials = Materials::load(MATERIALS_PATH).unwrap_or_else(|e| {
+            println!(
+                "Unable to load materials, every surface will look the same. Reason: {}",
+                e
+            );
+            Default::default()
+        });
+
+        let particle_effects =
+            ParticleEffects::load(EFFECTS_PATH, Some(engine.resource_manager.clone()))
+                .unwrap_or_else(|e| {
+                    println!("Unable to load effects, there will be none. Reason: {}", e);
+                    Default::default()
+                });
+
+        let tracers = Tracers::load(TRACERS_PATH).unwrap_or_else(|e| {
+            println!(
+                "Unable to load tracers, every shot will look the same. Reason: {}",
+                e
+            );
+            Default::default()
+        });
+
+        // Answer LAN discovery queries and remote admins only if we're hosting a game.
+        let mut discovery = None;
+        let mut rcon = None;
+        if let Some(host) = options.host {
+            if !host.is_empty() {
+                console.cvars_mut().set("sv_hostname", &host).unwrap();
+            }
+            discovery = DiscoveryResponder::bind(DISCOVERY_PORT)
+                .map_err(|e| println!("Unable to start LAN discovery responder: {}", e))
+                .ok();
+            if let Some(password) = options.rcon_password.filter(|p| !p.is_empty()) {
+                rcon = Rcon::bind(RCON_PORT, password, "rcon_audit.log")
+                    .map_err(|e| println!("Unable to start remote console: {}", e))
+                    .ok();
+            }
+        }
+
+        let mut game = Self {
+            // Level is loaded anyway, it serves as a background of the main menu.
+            state: GameState::MainMenu,
+            player,
+            scene: engine.scenes.add(scene),
+            map,
+            next_map: None,
+            weapons,
+            navmesh,
+            covers,
+            barrels,
+            effects,
+            bots: Default::default(),
+            next_bot_id: 1,
+            noises: Default::default(),
+            behavior,
+            materials,
+            particle_effects,
+            tracers,
+            weapon_kinds,
+            decals: Default::default(),
+            shells: Default::default(),
+            grenades: Default::default(),
+            receiver,
+            sender,
+            console,
+            console_ui,
+            hud,
+            menu,
+            quit_requested: false,
+            focused: true,
+            cursor_grabbed: false,
+            discovery,
+            rcon,
+        };
+        game.enter_state(engine, GameState::MainMenu);
+        game
+    }
+
+    pub fn state(&self) -> GameState {
+        self.state
+    }
+
+    pub fn quit_requested(&self) -> bool {
+        self.quit_requested
+    }
+
+    fn enter_state(&mut self, engine: &GameEngine, state: GameState) {
+        self.state = state;
+        if !state.is_simulating() {
+            // Otherwise player will keep running after the game is resumed.
+            self.player.reset_input();
+        }
+        self.menu.set_state(&engine.user_interface, state);
+        self.hud
+            .set_visible(&engine.user_interface, state != GameState::MainMenu);
+    }
+
+    // Cursor is hidden and confined to the window only while the player controls the
+    // character, any menu, the console or another window gets it back.
+    fn update_cursor(&mut self, engine: &GameEngine) {
+        let grab = self.state.is_simulating() && self.focused && !self.console_ui.is_visible();
+        if grab != self.cursor_grabbed {
+            let window = engine.get_window();
+            if let Err(e) = window.set_cursor_grab(grab) {
+                println!("Unable to grab cursor: {}", e);
+            }
+            window.set_cursor_visible(!grab);
+            self.cursor_grabbed = grab;
+        }
+    }
+
+    fn handle_state_event(&mut self, engine: &GameEngine, event: StateEvent) {
+        if let Some(state) = self.state.next(event) {
+            self.enter_state(engine, state);
+        }
+    }
+
+    pub fn timestep(&self) -> f32 {
+        1.0 / self.console.cvars().int("sv_tickrate").clamp(10, 1000) as f32
+    }
+
+    pub fn time_scale(&self) -> f32 {
+        self.console.cvars().float("timescale").clamp(0.01, 10.0)
+    }
+
+    fn print(&mut self, engine: &GameEngine, text: &str) {
+        self.console_ui.print(&engine.user_interface, text);
+    }
+
+    // Returns true if event was consumed by the game and must not be passed anywhere else.
+    pub fn process_input_event(&mut self, engine: &mut GameEngine, event: &Event<()>) -> bool {
+        if let Event::WindowEvent {
+            event: WindowEvent::Focused(focused),
+            ..
+        } = event
+        {
+            self.focused = *focused;
+            if !focused {
+                // Key releases will go to another window, so nothing must stay pressed.
+                self.player.reset_input();
+                if self.console.cvars().bool("cl_pause_on_focus_loss")
+                    && self.state == GameState::Playing
+                {
+                    self.handle_state_event(engine, StateEvent::TogglePause);
+                }
+            }
+            self.update_cursor(engine);
+        }
+
+        // Menus are built from UI widgets, so they need OS events to work.
+        if let Event::WindowEvent { event, .. } = event {
+            if let Some(os_event) = translate_event(event) {
+                engine.user_interface.process_os_event(&os_event);
+            }
+        }
+
+        let console_was_visible = self.console_ui.is_visible();
+        if self
+            .console_ui
+            .process_input_event(&engine.user_interface, &mut self.console, event)
+        {
+            if !console_was_visible {
+                // Otherwise player will keep running while the console is open.
+                self.player.reset_input();
+            }
+            return true;
+        }
+        if let Event::WindowEvent {
+            event: WindowEvent::KeyboardInput { input, .. },
+            ..
+        } = event
+        {
+            if input.state == ElementState::Pressed
+                && input.virtual_keycode == Some(VirtualKeyCode::Escape)
+            {
+                self.handle_state_event(engine, StateEvent::TogglePause);
+                return true;
+            }
+        }
+        // Mouse look works only with grabbed cursor, otherwise moving the cursor over
+        // other windows would turn the camera.
+        if self.state.is_simulating() && self.cursor_grabbed {
+            self.player.process_input_event(event);
+        }
+        false
+    }
+
+    pub fn resize(&mut self, engine: &GameEngine, width: f32, height: f32) {
+        self.console_ui
+            .resize(&engine.user_interface, width, height);
+        self.menu.resize(&engine.user_interface, width, height);
+        self.hud.resize(&engine.user_interface, width, height);
+    }
+
+    async fn load_level(
+        engine: &mut GameEngine,
+        map: &str,
+        sender: Sender<Message>,
+        weapon_kind: &WeaponKind,
+    ) -> Option<(
+        Scene,
+        Player,
+        Pool<Weapon>,
+        Navmesh,
+        CoverPoints,
+        Barrels,
+        Effects,
+    )> {
+        let mut scene = Scene::new();
         engine
             .resource_manager
-            .request_model("assets/models/scene.rgs")
+            .request_model(format!("assets/models/{}.rgs", map))
             .await
-            .unwrap()
+            .ok()?
             .instantiate_geometry(&mut scene);
-        let (sender, receiver) = mpsc::channel();
-        let mut player =
-            Player::new(&mut scene, engine.resource_manager.clone(), sender.clone()).await;
-        let weapon = Weapon::new(&mut scene, engine.resource_manager.clone()).await;
+        // Navmesh is built before any character is added, everything that is bound to a
+        // dynamic body is not a part of static geometry.
+        scene.graph.update_hierarchical_data();
+        let (physics, binder) = (&scene.physics, &scene.physics_binder);
+        let is_static = |node| navmesh::is_static_node(physics, binder, node);
+        let navmesh = navmesh::build_navmesh(&mut scene.graph, &Default::default(), is_static);
+        let covers = CoverPoints::build(
+            &scene.graph,
+            &navmesh,
+            &navmesh::static_triangles(&scene.graph, is_static),
+        );
+        let barrels = Barrels::find(&scene);
+        let effects = Effects::new(&mut scene.graph);
+        let mut player = Player::new(&mut scene, engine.resource_manager.clone(), sender).await;
+        let mut weapon =
+            match Weapon::new(&mut scene, engine.resource_manager.clone(), weapon_kind).await {
+                Ok(weapon) => weapon,
+                Err(e) => {
+                    println!("Unable to load weapon of the player. Reason: {}", e);
+                    return None;
+                }
+            };
+        weapon.set_owner(layers::PLAYER);
         scene.graph.link_nodes(weapon.model(), player.weapon_pivot);
         let mut weapons = Pool::new();
         player.weapon = weapons.spawn(weapon);
-        Self {
-            player,
-            scene: engine.scenes.add(scene),
-            weapons,
-            receiver,
-            sender,
+        Some((scene, player, weapons, navmesh, covers, barrels, effects))
+    }
+
+    fn change_map(&mut self, engine: &mut GameEngine, map: String) -> bool {
+        // Player keeps the weapon on the new map.
+        let weapon_kind = self.weapons[self.player.weapon].kind().clone();
+        match rg3d::futures::executor::block_on(Self::load_level(
+            engine,
+            &map,
+            self.sender.clone(),
+            &weapon_kind,
+        )) {
+            Some((scene, player, weapons, navmesh, covers, barrels, effects)) => {
+                engine.scenes.remove(self.scene);
+                self.scene = engine.scenes.add(scene);
+                self.player = player;
+                self.weapons = weapons;
+                self.navmesh = navmesh;
+                self.covers = covers;
+                self.barrels = barrels;
+                self.effects = effects;
+                self.decals.clear();
+                self.shells.clear();
+                self.grenades.clear();
+                self.bots.clear();
+                self.map = map;
+                true
+            }
+            None => {
+                self.print(engine, &format!("Unable to load map {}", map));
+                false
+            }
+        }
+    }
+
+    fn server_info(&self) -> ServerInfo {
+        ServerInfo {
+            name: self.console.cvars().string("sv_hostname").to_owned(),
+            map: self.map.clone(),
+            mode: "sandbox".to_owned(),
+            players: 1 + self.bots.alive_count() as u32,
+            max_players: 1 + self.console.cvars().int("sv_maxbots").max(0) as u32,
+            protocol_version: PROTOCOL_VERSION,
+        }
+    }
+
+    fn add_bot(&mut self, engine: &mut GameEngine) {
+        let cvars = self.console.cvars();
+        if self.bots.alive_count() as i32 >= cvars.int("sv_maxbots") {
+            self.print(engine, "Bot limit reached");
+            return;
+        }
+        let mut rng = rand::thread_rng();
+        let radius = cvars.float("bot_spawn_radius").max(0.1);
+        let position = Vector3::new(
+            rng.gen_range(-radius..radius),
+            1.0,
+            rng.gen_range(-radius..radius),
+        );
+        let difficulty = match Difficulty::from_cvars(cvars, cvars.string("bot_difficulty")) {
+            Ok(difficulty) => difficulty,
+            Err(e) => {
+                self.print(engine, &e);
+                return;
+            }
+        };
+        let fov = cvars.float("bot_fov").clamp(1.0, 360.0).to_radians();
+        let name = format!("Bot{}", self.next_bot_id);
+        let spawn_number = self.next_bot_id;
+        self.next_bot_id += 1;
+        let scene = &mut engine.scenes[self.scene];
+        // Bots use the same weapon as the player.
+        let mut weapon = match rg3d::futures::executor::block_on(Weapon::new(
+            scene,
+            engine.resource_manager.clone(),
+            self.weapon_kinds.first(),
+        )) {
+            Ok(weapon) => weapon,
+            Err(e) => {
+                self.print(engine, &e);
+                return;
+            }
+        };
+        let mut bot = Bot::new(
+            scene,
+            name,
+            position,
+            fov,
+            difficulty,
+            self.sender.clone(),
+            layers::BOT,
+        );
+        weapon.set_owner(layers::BOT);
+        scene.graph.link_nodes(weapon.model(), bot.weapon_pivot);
+        bot.weapon = self.weapons.spawn(weapon);
+        bot.spawn_number = spawn_number;
+        let _ = self.bots.spawn(bot);
+    }
+
+    // Removes the bot with the name or the one added last.
+    fn remove_bot(&mut self, engine: &mut GameEngine, name: Option<&str>) -> bool {
+        let handle = self
+            .bots
+            .pair_iter()
+            .filter(|(_, bot)| name.is_none_or(|name| bot.name == name))
+            .max_by_key(|(_, bot)| bot.spawn_number)
+            .map(|(handle, _)| handle);
+        match handle {
+            Some(handle) => {
+                let mut bot = self.bots.free(handle);
+                bot.clean_up(&mut engine.scenes[self.scene]);
+                self.weapons.free(bot.weapon);
+                self.covers.release(&bot.name);
+                true
+            }
+            None => false,
         }
     }
 
     pub fn update(&mut self, engine: &mut GameEngine, dt: f32) {
-        self.player.update(&mut engine.scenes[self.scene]);
-        for weapon in self.weapons.iter_mut() {
-            weapon.update(dt, &mut engine.scenes[self.scene].graph)
+        if let Some(discovery) = self
+            .discovery
+            .as_ref()
+            .filter(|_| self.console.cvars().bool("sv_lan_visible"))
+        {
+            let info = self.server_info();
+            if let Err(e) = discovery.poll(&info) {
+                println!("LAN discovery failed: {}", e);
+            }
+        }
+        if let Some(rcon) = self.rcon.as_mut() {
+            rcon.poll(&mut self.console);
         }
+        for line in self.particle_effects.update(dt) {
+            self.print(engine, &line);
+        }
+        while let Some(message) = engine.user_interface.poll_message() {
+            match self.menu.handle_ui_message(&message) {
+                Some(MenuAction::Event(event)) => self.handle_state_event(engine, *event),
+                Some(MenuAction::Quit) => self.quit_requested = true,
+                None => (),
+            }
+        }
+
+        match self.state {
+            GameState::Loading => {
+                let map = self.next_map.take().unwrap_or_else(|| self.map.clone());
+                let event = if self.change_map(engine, map) {
+                    StateEvent::LevelLoaded
+                } else {
+                    StateEvent::LoadFailed
+                };
+                self.handle_state_event(engine, event);
+            }
+            GameState::Playing => {
+                let scene = &mut engine.scenes[self.scene];
+                // Time scale is applied by changing time step of the physics.
+                scene.physics.integration_parameters.dt = dt;
+
+                self.player.update(scene);
+                if self.player.is_walking(scene) {
+                    let position = scene.graph[self.player.pivot].global_position();
+                    let surface = self.materials.below(scene, position);
+                    self.noises.push(Noise {
+                        position,
+                        radius: self.console.cvars().float("g_footstep_noise_radius")
+                            * surface.footstep_loudness,
+                        kind: NoiseKind::Footstep,
+                        emitter: self.player.collider,
+                    });
+                }
+                if let Some(trace) = self.update_bots(scene, dt) {
+                    self.console_ui.print(&engine.user_interface, &trace);
+                }
+                self.noises.clear();
+                for weapon in self.weapons.iter_mut() {
+                    weapon.update(dt, &mut engine.scenes[self.scene].graph)
+                }
+                self.effects
+                    .update(&mut engine.scenes[self.scene].graph, dt);
+                self.shells.update(&mut engine.scenes[self.scene], dt);
+                // Grenade cooked for too long blows up in hand.
+                let fuse = self.console.cvars().float("g_grenade_fuse");
+                if self.grenades.cooked().is_some_and(|time| time >= fuse) {
+                    self.throw_grenade(engine);
+                }
+                for center in self.grenades.update(&mut engine.scenes[self.scene], dt) {
+                    self.explode(engine, center, None, None);
+                }
+                for center in self.barrels.update(dt, &mut engine.scenes[self.scene]) {
+                    self.explode(engine, center, None, None);
+                }
+                self.update_hud(engine, dt);
+            }
+            _ => (),
+        }
+        self.update_cursor(engine);
+
         while let Ok(message) = self.receiver.try_recv() {
             match message {
-                Message::ShootWeapon { weapon } => {
-                    self.shoot_weapon(weapon, engine);
+                Message::ShootWeapon { weapon } => self.shoot_weapon(weapon, engine),
+                // Weapon may be gone since the message was sent.
+                Message::ReloadWeapon { weapon } => {
+                    if let Some(weapon) = self.weapons.try_borrow_mut(weapon) {
+                        weapon.reload()
+                    }
+                }
+                Message::CookGrenade => self.grenades.cook(),
+                Message::ThrowGrenade => self.throw_grenade(engine),
+                Message::Kick { name } => {
+                    // The only remote players for now are bots.
+                    if !self.remove_bot(engine, Some(&name)) {
+                        self.print(engine, &format!("No such player {}", name));
+                    }
+                }
+                Message::ChangeMap { name } => {
+                    self.next_map = Some(name);
+                    self.handle_state_event(engine, StateEvent::LoadLevel);
+                }
+                Message::AddBot => self.add_bot(engine),
+                Message::RemoveBot { name } => {
+                    if !self.remove_bot(engine, name.as_deref()) {
+                        self.print(engine, "No bot to remove");
+                    }
+                }
+                Message::ToggleGod => {
+                    self.player.god = !self.player.god;
+                    let text = format!("god mode {}", if self.player.god { "ON" } else { "OFF" });
+                    self.print(engine, &text);
+                }
+                Message::ToggleNoclip => {
+                    let noclip = !self.player.is_noclip();
+                    self.player
+                        .set_noclip(&mut engine.scenes[self.scene], noclip);
+                    let text = format!("noclip {}", if noclip { "ON" } else { "OFF" });
+                    self.print(engine, &text);
+                }
+                Message::Give { item, amount } => match item.as_str() {
+                    "health" => {
+                        self.player.health = (self.player.health
+                            + amount.unwrap_or(player::MAX_HEALTH))
+                        .min(player::MAX_HEALTH);
+                    }
+                    "armor" => {
+                        self.player.armor = (self.player.armor
+                            + amount.unwrap_or(player::MAX_ARMOR))
+                        .min(player::MAX_ARMOR);
+                    }
+                    "ammo" => self.weapons[self.player.weapon]
+                        .add_ammo(amount.unwrap_or(90.0).max(0.0) as u32),
+                    _ => self.print(engine, &format!("Unknown item {}", item)),
+                },
+                Message::SwitchWeapon { name } => self.switch_weapon(engine, &name),
+                Message::DamagePlayer { amount, region } => {
+                    if self.player.damage(amount) {
+                        if region == Some(Region::Head) {
+                            self.print(engine, "You died from a headshot");
+                        } else {
+                            self.print(engine, "You died");
+                        }
+                        self.handle_state_event(engine, StateEvent::PlayerDied);
+                    }
+                }
+                Message::DamageBody { body, amount } => self.damage_body(engine, body, amount),
+                Message::Explode {
+                    center,
+                    radius,
+                    damage,
+                } => self.explode(engine, center, radius, damage),
+                Message::DumpFalloff { name } => {
+                    let kind = match name {
+                        Some(name) => self.weapon_kinds.find(&name),
+                        None => Some(self.weapons[self.player.weapon].kind()),
+                    };
+                    let lines = match kind {
+                        Some(kind) => kind.describe_falloff(
+                            self.console.cvars().float("weapon_damage"),
+                            self.console.cvars().float("g_impact_force"),
+                        ),
+                        None => vec!["No such weapon".to_owned()],
+                    };
+                    for line in lines {
+                        self.print(engine, &line);
+                    }
+                }
+                Message::DumpPerception { name } => {
+                    let lines = self
+                        .bots
+                        .iter()
+                        .filter(|bot| name.as_deref().is_none_or(|name| bot.name == name))
+                        .map(|bot| format!("{}: {}", bot.name, bot.perception.describe()))
+                        .collect::<Vec<_>>();
+                    if lines.is_empty() {
+                        self.print(engine, "No such bot");
+                    }
+                    for line in lines {
+                        self.print(engine, &line);
+                    }
                 }
             }
         }
     }
 
-    fn shoot_weapon(&mut self, weapon: Handle<Weapon>, engine: &mut GameEngine) {
-        let weapon = &mut self.weapons[weapon];
+    // Every bot perceives the world, decides what to do and then moves. Returns what the bot
+    // named by bot_trace decided, if there is one.
+    fn update_bots(&mut self, scene: &mut Scene, dt: f32) -> Option<String> {
+        let targets = [Target {
+            position: scene.graph[self.player.pivot].global_position(),
+            collider: self.player.collider,
+        }];
+        let traced = self.console.cvars().string("bot_trace");
+        let mut traced_line = None;
+        for bot in self.bots.iter_mut() {
+            bot.perceive(dt, scene, &targets, &self.noises);
+            let mut trace = Vec::new();
+            let status = bot.think(
+                &self.behavior,
+                scene,
+                &self.navmesh,
+                &mut self.covers,
+                &self.weapons[bot.weapon],
+                Some(&mut trace).filter(|_| bot.name == traced),
+            );
+            if !trace.is_empty() {
+                traced_line = Some(format!("{} {:?}: {}", bot.name, status, trace.join(", ")));
+            }
+            bot.update(dt, scene);
+        }
+        traced_line
+    }
+
+    fn update_hud(&mut self, engine: &GameEngine, dt: f32) {
+        let scene = &engine.scenes[self.scene];
+        // Show where a cooked grenade would fly, points behind the camera cannot be projected.
+        let mut grenade_fuse = None;
+        let mut trajectory = Vec::new();
+        if let Some(cooked) = self.grenades.cooked() {
+            let throw = self.grenade_throw(scene, cooked);
+            grenade_fuse = Some(throw.fuse.max(0.0));
+            let mut points = Vec::new();
+            grenade::trajectory(scene, &throw, &mut points);
+            let camera = scene.graph[self.player.camera].as_camera();
+            let (eye, look) = (camera.global_position(), camera.look_vector());
+            let (width, height) = engine.renderer.get_frame_size();
+            let frame_size = Vector2::new(width as f32, height as f32);
+            trajectory = points
+                .iter()
+                .filter(|point| (*point - eye).dot(&look) > 0.0)
+                .filter_map(|point| camera.project(*point, frame_size))
+                .collect();
+        }
+        let weapon = &self.weapons[self.player.weapon];
+        let state = HudState {
+            spread: weapon.spread(),
+            fov: scene.graph[self.player.camera].as_camera().fov(),
+            health: self.player.health,
+            armor: self.player.armor,
+            ammo: weapon.ammo(),
+            reserve_ammo: weapon.reserve_ammo(),
+            reloading: weapon.is_reloading(),
+            grenade_fuse,
+            trajectory,
+        };
+        self.hud.update(&engine.user_interface, &state, dt);
+    }
+
+    fn throw_grenade(&mut self, engine: &mut GameEngine) {
+        if let Some(cooked) = self.grenades.stop_cooking() {
+            let scene = &mut engine.scenes[self.scene];
+            let throw = self.grenade_throw(scene, cooked);
+            self.grenades.throw(scene, &throw);
+        }
+    }
+
+    // How the grenade in the player's hand would be thrown right now.
+    fn grenade_throw(&self, scene: &Scene, cooked: f32) -> Throw {
+        let cvars = self.console.cvars();
+        let camera = &scene.graph[self.player.camera];
+        let velocity = scene
+            .physics
+            .bodies
+            .get(self.player.rigid_body.into())
+            .map_or_else(Vector3::default, |body| *body.linvel());
+        Throw::new(
+            camera.global_position(),
+            camera.look_vector(),
+            velocity,
+            cvars.float("g_grenade_throw_speed"),
+            cvars.float("g_grenade_fuse") - cooked,
+            cvars.float("g_grenade_restitution").clamp(0.0, 1.0),
+        )
+    }
+
+    fn shoot_weapon(&mut self, weapon_handle: Handle<Weapon>, engine: &mut GameEngine) {
+        // Bots free their weapons when they die or leave, shots may still be queued.
+        let weapon = match self.weapons.try_borrow_mut(weapon_handle) {
+            Some(weapon) => weapon,
+            None => return,
+        };
+        let cvars = self.console.cvars();
+        // Bots shoot from the same weapons as the player does.
+        let is_player_shooting = weapon_handle == self.player.weapon;
 
         if weapon.can_shoot() {
-            weapon.shoot();
+            weapon.shoot(cvars);
 
             let scene = &mut engine.scenes[self.scene];
 
-            let weapon_model = &scene.graph[weapon.model()];
+            let emitter = if is_player_shooting {
+                self.player.collider
+            } else {
+                self.bots
+                    .iter()
+                    .find(|bot| bot.weapon == weapon_handle)
+                    .map_or_else(Default::default, |bot| bot.collider)
+            };
+            self.noises.push(Noise {
+                position: scene.graph[weapon.shot_point()].global_position(),
+                radius: cvars.float("g_gunshot_noise_radius"),
+                kind: NoiseKind::Gunshot,
+                emitter,
+            });
 
-            // Make a ray that starts at the weapon's position in the world and look toward
-            // "look" vector of the weapon.
-            let ray = Ray::new(
-                scene.graph[weapon.shot_point()].global_position(),
-                weapon_model.look_vector().scale(1000.0),
+            let kind = weapon.kind();
+            let tracer = self.tracers.find(&kind.name, &kind.ammo);
+            let round = weapon.rounds_fired();
+            let pellets = weapon.cast_shot(
+                scene,
+                &self.materials,
+                cvars.float("weapon_penetration"),
+                cvars.int("weapon_pellet_seed") as u64,
+                &mut rand::thread_rng(),
+            );
+            // Every pellet starts at the muzzle, the first one flies where the weapon aims.
+            let muzzle = pellets[0].ray;
+            self.effects.spawn(
+                &mut scene.graph,
+                &self.particle_effects,
+                "muzzle_flash",
+                muzzle.origin,
+                UnitQuaternion::face_towards(&muzzle.dir, &Vector3::y()),
+            );
+            self.effects.flash(&mut scene.graph, muzzle.origin);
+            let (position, orientation) = weapon.ejection(&scene.graph);
+            self.shells.eject(
+                scene,
+                &mut rand::thread_rng(),
+                position,
+                orientation,
+                cvars.float("cl_shell_lifetime"),
+                cvars.int("cl_max_shells").max(0) as usize,
             );
 
-            let mut intersections = Vec::new();
+            let mut killed_bots = Vec::new();
 
-            scene.physics.cast_ray(
-                RayCastOptions {
-                    ray,
-                    max_len: ray.dir.norm(),
-                    groups: Default::default(),
-                    sort_results: true, // We need intersections to be sorted from closest to furthest.
-                },
-                &mut intersections,
-            );
+            for pellet in pellets.iter() {
+                for hit in pellet.hits.iter() {
+                    let intersection = &hit.intersection;
+                    let (damage, impulse) = pellet.impact(hit, kind, cvars);
+                    if let Some(region) = self.player.hitboxes.region_of(intersection.collider) {
+                        self.sender
+                            .send(Message::DamagePlayer {
+                                amount: damage * region.damage_multiplier(cvars),
+                                region: Some(region),
+                            })
+                            .unwrap();
+                    }
 
-            // Ignore intersections with player's capsule.
-            let trail_length = if let Some(intersection) = intersections
-                .iter()
-                .find(|i| i.collider != self.player.collider)
-            {
-                //
-                // TODO: Add code to handle intersections with bots.
-                //
-
-                // For now just apply some force at the point of impact.
-                let collider = scene
-                    .physics
-                    .colliders
-                    .get(intersection.collider.into())
-                    .unwrap();
-                scene
-                    .physics
-                    .bodies
-                    .get_mut(collider.parent())
-                    .unwrap()
-                    .apply_force_at_point(
-                        ray.dir.normalize().scale(10.0),
-                        intersection.position,
-                        true,
+                    if let Some((handle, bot, region)) =
+                        self.bots.pair_iter_mut().find_map(|(handle, bot)| {
+                            bot.hitboxes
+                                .region_of(intersection.collider)
+                                .map(|region| (handle, bot, region))
+                        })
+                    {
+                        let kill = bot.damage(damage * region.damage_multiplier(cvars));
+                        let headshot = region == Region::Head;
+                        // Other pellets may hit the bot after it is dead.
+                        if kill && !killed_bots.iter().any(|(killed, _)| *killed == handle) {
+                            killed_bots.push((handle, headshot));
+                        }
+                        if is_player_shooting {
+                            self.hud
+                                .show_hit_marker(&engine.user_interface, kill, headshot);
+                        }
+                    }
+
+                    let collider = scene
+                        .physics
+                        .colliders
+                        .get(intersection.collider.into())
+                        .unwrap();
+                    // Barrel explodes on next update, bodies around it are pushed then.
+                    self.barrels.damage(collider.parent().into(), damage, 0.0);
+
+                    // Push the body at the point of impact.
+                    scene
+                        .physics
+                        .bodies
+                        .get_mut(collider.parent())
+                        .unwrap()
+                        .apply_force_at_point(
+                            pellet.ray.dir.normalize().scale(impulse),
+                            intersection.position,
+                            true,
+                        );
+
+                    // Pellet which went through leaves a hole on the other side too.
+                    let material = self.materials.of_collider(scene, intersection.collider);
+                    for intersection in std::iter::once(intersection).chain(hit.exit.as_ref()) {
+                        self.effects.add_impact(
+                            &self.particle_effects,
+                            &material.impact_effect,
+                            intersection.position.coords,
+                            intersection.normal,
+                        );
+                        if let Some((texture, size)) = material.decal.as_ref() {
+                            Self::add_decal(
+                                scene,
+                                &mut self.decals,
+                                engine.resource_manager.clone(),
+                                texture,
+                                intersection,
+                                *size,
+                                cvars.int("cl_max_decals"),
+                            );
+                        }
+                    }
+                }
+            }
+            self.effects
+                .play_impacts(&mut scene.graph, &self.particle_effects);
+
+            if tracer.is_drawn(round) {
+                for pellet in pellets.iter().take(tracer.pellets) {
+                    // Trail ends where the pellet stopped, otherwise it is just the ray length.
+                    let trail_length = match pellet.hits.last() {
+                        Some(hit) if hit.exit.is_none() => {
+                            (hit.intersection.position.coords - pellet.ray.origin).norm()
+                        }
+                        _ => pellet.ray.dir.norm(),
+                    };
+                    self.effects.shot_trail(
+                        &mut scene.graph,
+                        pellet.ray.origin,
+                        pellet.ray.dir,
+                        trail_length,
+                        tracer,
+                        cvars.string("cl_tracer_palette") == "colorblind",
                     );
+                }
+            }
 
-                let effect_orientation = if intersection.normal.normalize() == Vector3::y() {
-                    UnitQuaternion::from_axis_angle(&Vector3::y_axis(), 0.0)
-                } else {
-                    UnitQuaternion::face_towards(&intersection.normal, &Vector3::y())
-                };
-                Self::create_bullet_impact(
-                    &mut scene.graph,
-                    engine.resource_manager.clone(),
-                    intersection.position.coords,
-                    effect_orientation,
+            for (handle, headshot) in killed_bots {
+                self.kill_bot(engine, handle, headshot);
+            }
+        }
+    }
+
+    // Player gets a new weapon of the kind with full ammo instead of the current one.
+    fn switch_weapon(&mut self, engine: &mut GameEngine, name: &str) {
+        let kind = match self.weapon_kinds.find(name) {
+            Some(kind) => kind,
+            None => {
+                let names = self.weapon_kinds.names().collect::<Vec<_>>().join(", ");
+                self.print(
+                    engine,
+                    &format!("Unknown weapon {}, weapons: {}", name, names),
                 );
+                return;
+            }
+        };
+        let scene = &mut engine.scenes[self.scene];
+        // Player keeps the current weapon if the new one cannot be loaded.
+        let mut weapon = match rg3d::futures::executor::block_on(Weapon::new(
+            scene,
+            engine.resource_manager.clone(),
+            kind,
+        )) {
+            Ok(weapon) => weapon,
+            Err(e) => {
+                self.print(engine, &e);
+                return;
+            }
+        };
+        let old = self.weapons.free(self.player.weapon);
+        scene.remove_node(old.model());
+        weapon.set_owner(layers::PLAYER);
+        scene
+            .graph
+            .link_nodes(weapon.model(), self.player.weapon_pivot);
+        self.player.weapon = self.weapons.spawn(weapon);
+    }
 
-                // Trail length will be the length of line between intersection point and ray origin.
-                (intersection.position.coords - ray.origin).norm()
-            } else {
-                // Otherwise trail length will be just the ray length.
-                ray.dir.norm()
-            };
+    fn kill_bot(&mut self, engine: &mut GameEngine, handle: Handle<Bot>, headshot: bool) {
+        let mut bot = self.bots.free(handle);
+        bot.clean_up(&mut engine.scenes[self.scene]);
+        self.weapons.free(bot.weapon);
+        self.covers.release(&bot.name);
+        let text = if headshot {
+            format!("{} was killed by a headshot", bot.name)
+        } else {
+            format!("{} was killed", bot.name)
+        };
+        self.print(engine, &text);
+    }
+
+    // Blast with the visual effect, parameters which are not given are taken from cvars.
+    fn explode(
+        &mut self,
+        engine: &mut GameEngine,
+        center: Vector3<f32>,
+        radius: Option<f32>,
+        damage: Option<f32>,
+    ) {
+        let cvars = self.console.cvars();
+        let falloff = cvars
+            .string("g_explosion_falloff")
+            .parse()
+            .unwrap_or_else(|e| {
+                println!("{}, using linear falloff", e);
+                Falloff::Linear
+            });
+        let scene = &mut engine.scenes[self.scene];
+        explosion::explode(
+            scene,
+            &self.sender,
+            center,
+            radius
+                .unwrap_or_else(|| cvars.float("g_explosion_radius"))
+                .max(0.1),
+            cvars.float("g_explosion_impulse"),
+            damage.unwrap_or_else(|| cvars.float("g_explosion_damage")),
+            falloff,
+        );
+        self.effects.spawn(
+            &mut scene.graph,
+            &self.particle_effects,
+            "explosion",
+            center,
+            UnitQuaternion::identity(),
+        );
 
-            Self::create_shot_trail(&mut scene.graph, ray.origin, ray.dir, trail_length);
+        // Scorch the ground right below the blast.
+        let mut intersections = Vec::new();
+        scene.physics.cast_ray(
+            RayCastOptions {
+                ray: Ray::new(center + Vector3::new(0.0, 0.5, 0.0), -Vector3::y()),
+                max_len: 2.0,
+                groups: layers::query(layers::PROJECTILE, layers::WORLD),
+                sort_results: true,
+            },
+            &mut intersections,
+        );
+        if let Some(intersection) = intersections.first() {
+            Self::add_decal(
+                scene,
+                &mut self.decals,
+                engine.resource_manager.clone(),
+                SCORCH_TEXTURE,
+                intersection,
+                radius.unwrap_or_else(|| cvars.float("g_explosion_radius")) * 0.5,
+                cvars.int("cl_max_decals"),
+            );
         }
     }
 
-    fn create_shot_trail(
-        graph: &mut Graph,
-        origin: Vector3<f32>,
-        direction: Vector3<f32>,
-        trail_length: f32,
-    ) {
-        let transform = TransformBuilder::new()
-            .with_local_position(origin)
-            .with_local_scale(Vector3::new(0.0025, 0.0025, trail_length))
-            .with_local_rotation(UnitQuaternion::face_towards(&direction, &Vector3::y()))
-            .build();
-
-        // Create unit cylinder with caps that faces toward Z axis.
-        let shape = Arc::new(RwLock::new(SurfaceSharedData::make_cylinder(
-            6,     // Count of sides
-            1.0,   // Radius
-            1.0,   // Height
-            false, // No caps are needed.
-            // Rotate vertical cylinder around X axis to make it face towards Z axis
-            UnitQuaternion::from_axis_angle(&Vector3::x_axis(), 90.0f32.to_radians())
-                .to_homogeneous(),
-        )));
-
-        MeshBuilder::new(
-            BaseBuilder::new()
-                .with_local_transform(transform)
-                // Shot trail should live ~0.25 seconds, after that it will be automatically
-                // destroyed.
-                .with_lifetime(0.25),
-        )
-        .with_surfaces(vec![SurfaceBuilder::new(shape)
-            // Set yellow-ish color.
-            .with_color(Color::from_rgba(255, 255, 0, 120))
-            .build()])
-        // Do not cast shadows.
-        .with_cast_shadows(false)
-        // Make sure to set Forward render path, otherwise the object won't be
-        // transparent.
-        .with_render_path(RenderPath::Forward)
-        .build(graph);
-    }
-
-    fn create_bullet_impact(
-        graph: &mut Graph,
+    // Decal sticks to the node of the body that was hit, so it moves together with it.
+    fn add_decal(
+        scene: &mut Scene,
+        decals: &mut Decals,
         resource_manager: ResourceManager,
-        pos: Vector3<f32>,
-        orientation: UnitQuaternion<f32>,
-    ) -> Handle<Node> {
-        // Create sphere emitter first.
-        let emitter = SphereEmitterBuilder::new(
-            BaseEmitterBuilder::new()
-                .with_max_particles(200)
-                .with_spawn_rate(1000)
-                .with_size_modifier_range(NumericRange::new(-0.01, -0.0125))
-                .with_size_range(NumericRange::new(0.0010, 0.025))
-                .with_x_velocity_range(NumericRange::new(-0.01, 0.01))
-                .with_y_velocity_range(NumericRange::new(0.030, 0.10))
-                .with_z_velocity_range(NumericRange::new(-0.01, 0.01))
-                .resurrect_particles(false),
-        )
-        .with_radius(0.01)
-        .build();
-
-        // Color gradient will be used to modify color of each particle over its lifetime.
-        let color_gradient = {
-            let mut gradient = ColorGradient::new();
-            gradient.add_point(GradientPoint::new(0.00, Color::from_rgba(255, 255, 0, 0)));
-            gradient.add_point(GradientPoint::new(0.05, Color::from_rgba(255, 160, 0, 255)));
-            gradient.add_point(GradientPoint::new(0.95, Color::from_rgba(255, 120, 0, 255)));
-            gradient.add_point(GradientPoint::new(1.00, Color::from_rgba(255, 60, 0, 0)));
-            gradient
-        };
+        texture: &str,
+        intersection: &Intersection,
+        size: f32,
+        limit: i32,
+    ) {
+        let parent = scene
+            .physics
+            .colliders
+            .get(intersection.collider.into())
+            .and_then(|collider| scene.physics_binder.node_of(collider.parent().into()))
+            .unwrap_or_else(|| scene.graph.get_root());
+        decals.add(
+            &mut scene.graph,
+            resource_manager,
+            texture,
+            parent,
+            intersection.position.coords,
+            intersection.normal,
+            size,
+            rand::thread_rng().gen_range(0.0..std::f32::consts::TAU),
+            limit.max(0) as usize,
+        );
+    }
 
-        // Create new transform to orient and position particle system.
-        let transform = TransformBuilder::new()
-            .with_local_position(pos)
-            .with_local_rotation(orientation)
-            .build();
-
-        // Finally create particle system with limited lifetime.
-        ParticleSystemBuilder::new(
-            BaseBuilder::new()
-                .with_lifetime(1.0)
-                .with_local_transform(transform),
-        )
-        .with_acceleration(Vector3::new(0.0, -10.0, 0.0))
-        .with_color_over_lifetime_gradient(color_gradient)
-        .with_emitters(vec![emitter])
-        // We'll use simple spark texture for each particle.
-        .with_texture(resource_manager.request_texture(Path::new("assets/textures/spark.png")))
-        .build(graph)
+    // Damage of an explosion, the body may belong to the player, a bot or a barrel.
+    fn damage_body(&mut self, engine: &mut GameEngine, body: RigidBodyHandle, amount: f32) {
+        if body == self.player.rigid_body && self.player.damage(amount) {
+            self.print(engine, "You died");
+            self.handle_state_event(engine, StateEvent::PlayerDied);
+        }
+        if let Some((handle, bot)) = self
+            .bots
+            .pair_iter_mut()
+            .find(|(_, bot)| bot.rigid_body == body)
+        {
+            if bot.damage(amount) {
+                self.kill_bot(engine, handle, false);
+            }
+        }
+        self.barrels.damage(body, amount, CHAIN_DELAY);
     }
 }
 
+// Executes autoexec script if there is one, returns its output or errors.
+fn run_autoexec(console: &mut CommandRegistry) -> Option<String> {
+    Path::new(AUTOEXEC_PATH)
+        .exists()
+        .then(|| match console.exec_script(AUTOEXEC_PATH) {
+            Ok(output) => output,
+            Err(errors) => errors,
+        })
+}
+
+// Prints every server that answered LAN discovery query.
+fn list_servers() {
+    match discovery::discover(DISCOVERY_PORT, Duration::from_secs(1)) {
+        Ok(servers) => {
+            for server in servers {
+                println!(
+                    "{} - {} [{}/{}] {} on {}",
+                    server.address,
+                    server.info.name,
+                    server.info.players,
+                    server.info.max_players,
+                    server.info.mode,
+                    server.info.map
+                );
+            }
+        }
+        Err(e) => println!("LAN discovery failed: {}", e),
+    }
+}
+
+// Loads a map without the engine, its physics stays in descriptors then.
+fn load_scene_headless(map: &str) -> Result<Scene, String> {
+    let path = format!("assets/models/{}.rgs", map);
+    let mut scene = Scene::default();
+    Visitor::load_binary(&path)
+        .and_then(|mut visitor| scene.visit("Scene", &mut visitor))
+        .map_err(|e| format!("Unable to load {}: {}", path, e))?;
+    scene.graph.update_hierarchical_data();
+    Ok(scene)
+}
+
+// Builds navmesh of a map and runs random path queries on it. It needs no window or renderer,
+// so it can be run anywhere to check that bots will be able to move around the map.
+fn navmesh_report(map: &str) {
+    const QUERIES: usize = 200;
+
+    let mut scene = match load_scene_headless(map) {
+        Ok(scene) => scene,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    let clock = time::Instant::now();
+    let (physics, binder) = (&scene.physics, &scene.physics_binder);
+    let is_static = |node| navmesh::is_static_node(physics, binder, node);
+    let navmesh = navmesh::build_navmesh(&mut scene.graph, &Default::default(), is_static);
+    println!(
+        "{}: {} vertices, {} triangles, built in {:?}",
+        map,
+        navmesh.vertex_count(),
+        navmesh.triangle_count(),
+        clock.elapsed()
+    );
+    let clock = time::Instant::now();
+    let covers = CoverPoints::build(
+        &scene.graph,
+        &navmesh,
+        &navmesh::static_triangles(&scene.graph, is_static),
+    );
+    let full = covers
+        .iter()
+        .filter(|point| point.height == CoverHeight::Full)
+        .count();
+    println!(
+        "{} cover points ({} full, {} crouch), built in {:?}",
+        covers.len(),
+        full,
+        covers.len() - full,
+        clock.elapsed()
+    );
+
+    // Fixed seed makes every run check the same queries.
+    let mut rng = StdRng::seed_from_u64(0);
+    let clock = time::Instant::now();
+    let (mut found, mut points, mut length) = (0, 0, 0.0);
+    for _ in 0..QUERIES {
+        let from = navmesh.random_point(&mut rng);
+        let to = navmesh.random_point(&mut rng);
+        if let Some(path) = from
+            .zip(to)
+            .and_then(|(from, to)| navmesh.find_path(from, to))
+        {
+            found += 1;
+            points += path.len();
+            length += path.windows(2).map(|w| (w[1] - w[0]).norm()).sum::<f32>();
+        }
+    }
+    println!(
+        "{}/{} paths found, {:.1} points and {:.1} m on average, {:?} per query",
+        found,
+        QUERIES,
+        points as f32 / found.max(1) as f32,
+        length / found.max(1) as f32,
+        clock.elapsed() / QUERIES as u32
+    );
+}
+
 fn main() {
+    let options = Options::parse();
+    if options.list_servers {
+        list_servers();
+        return;
+    }
+    if let Some(map) = options.navmesh_report.as_deref() {
+        navmesh_report(map);
+        return;
+    }
+    if options.bot_accuracy_report {
+        duel::accuracy_report();
+        return;
+    }
+    #[cfg(feature = "effects-benchmark")]
+    if options.effects_benchmark {
+        effects_benchmark::run();
+        return;
+    }
+
     // Configure main window first.
     let window_builder = WindowBuilder::new()
         .with_maximized(true)
@@ -283,7 +1330,7 @@ fn main() {
     let mut engine = GameEngine::new(window_builder, &event_loop, true).unwrap();
 
     // Initialize game instance. It is empty for now.
-    let mut game = rg3d::futures::executor::block_on(Game::new(&mut engine));
+    let mut game = rg3d::futures::executor::block_on(Game::new(&mut engine, options));
 
     // Run the event loop of the main window. which will respond to OS and window events and update
     // engine's state accordingly. Engine lets you to decide which event should be handled,
@@ -292,22 +1339,41 @@ fn main() {
 
     let mut elapsed_time = 0.0;
     event_loop.run(move |event, _, control_flow| {
-        game.player.process_input_event(&event);
+        // Game gets input first, everything it consumed must not go anywhere else.
+        if game.process_input_event(&mut engine, &event) {
+            return;
+        }
         match event {
             Event::MainEventsCleared => {
                 // This main game loop - it has fixed time step which means that game
                 // code will run at fixed speed even if renderer can't give you desired
                 // 60 fps.
+                let timestep = game.timestep();
                 let mut dt = clock.elapsed().as_secs_f32() - elapsed_time;
-                while dt >= TIMESTEP {
-                    dt -= TIMESTEP;
-                    elapsed_time += TIMESTEP;
+                while dt >= timestep {
+                    dt -= timestep;
+                    elapsed_time += timestep;
+
+                    // Time scale slows down or speeds up the game, but not the loop itself.
+                    let scaled_timestep = timestep * game.time_scale();
 
                     // Run our game's logic.
-                    game.update(&mut engine, dt);
+                    game.update(&mut engine, scaled_timestep);
 
-                    // Update engine each frame.
-                    engine.update(TIMESTEP);
+                    if game.state().is_simulating() {
+                        // Update engine each frame.
+                        engine.update(scaled_timestep);
+                    } else {
+                        // Scene is frozen, but menus must keep working.
+                        let (width, height) = engine.renderer.get_frame_size();
+                        engine
+                            .user_interface
+                            .update(Vector2::new(width as f32, height as f32), timestep);
+                    }
+                }
+
+                if game.quit_requested() {
+                    *control_flow = ControlFlow::Exit;
                 }
 
                 // Rendering must be explicitly requested and handled after RedrawRequested event is received.
@@ -315,21 +1381,16 @@ fn main() {
             }
             Event::RedrawRequested(_) => {
                 // Render at max speed - it is not tied to the game code.
-                engine.render(TIMESTEP).unwrap();
+                engine.render(game.timestep()).unwrap();
             }
             Event::WindowEvent { event, .. } => match event {
                 WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
-                WindowEvent::KeyboardInput { input, .. } => {
-                    // Exit game by hitting Escape.
-                    if let Some(VirtualKeyCode::Escape) = input.virtual_keycode {
-                        *control_flow = ControlFlow::Exit
-                    }
-                }
                 WindowEvent::Resized(size) => {
                     // It is very important to handle Resized event from window, because
                     // renderer knows nothing about window size - it must be notified
                     // directly when window size has changed.
                     engine.renderer.set_frame_size(size.into());
+                    game.resize(&engine, size.width as f32, size.height as f32);
                 }
                 _ => (),
             },
@@ -337,3 +1398,22 @@ fn main() {
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Bots must be able to get from one end of the shipped level to the other.
+    #[test]
+    fn shipped_scene_has_path_across() {
+        let mut scene = load_scene_headless("scene").unwrap();
+        let (physics, binder) = (&scene.physics, &scene.physics_binder);
+        let is_static = |node| navmesh::is_static_node(physics, binder, node);
+        let navmesh = navmesh::build_navmesh(&mut scene.graph, &Default::default(), is_static);
+        let from = Vector3::new(-12.0, 0.0, -20.0);
+        let to = Vector3::new(12.0, 0.0, 20.0);
+        let path = navmesh.find_path(from, to).unwrap();
+        assert!(path.len() >= 2);
+        assert!((path.last().unwrap().xz() - to.xz()).norm() < 0.5);
+    }
+}
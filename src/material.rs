@@ -0,0 +1,287 @@
+use std::path::Path;
+
+use rg3d::{
+    core::{algebra::Vector3, math::ray::Ray},
+    scene::{physics::RayCastOptions, ColliderHandle, Scene},
+};
+
+use crate::layers;
+
+/// What a surface is made of, it decides how the surface looks and sounds when it is hit or
+/// walked on.
+pub struct Material {
+    pub name: String,
+    // Words in node names which mean the node is made of the material.
+    matches: Vec<String>,
+    // Colliders on these layers are made of the material.
+    layers: u16,
+    // Name of the particle effect played where a bullet hits.
+    pub impact_effect: String,
+    // Texture and size of bullet holes, surfaces without it are not marked.
+    pub decal: Option<(String, f32)>,
+    // Penetration power a bullet loses going through a meter of the material.
+    pub resistance: f32,
+    pub footstep_loudness: f32,
+}
+
+impl Material {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            matches: Vec::new(),
+            layers: 0,
+            impact_effect: "impact".to_owned(),
+            decal: None,
+            // Nothing goes through unknown materials.
+            resistance: 1000.0,
+            footstep_loudness: 1.0,
+        }
+    }
+}
+
+/// Every material from the materials file, the first one is the default.
+pub struct Materials {
+    materials: Vec<Material>,
+}
+
+impl Default for Materials {
+    // Used when materials file cannot be loaded, everything is the same.
+    fn default() -> Self {
+        Self {
+            materials: vec![Material::new("default")],
+        }
+    }
+}
+
+impl Materials {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("unable to read {}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut materials: Vec<Material> = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with("//") || trimmed.starts_with('#') {
+                continue;
+            }
+            let error = |text: &str| Err(format!("line {}: {}", i + 1, text));
+            let tokens = trimmed.split_whitespace().collect::<Vec<_>>();
+            if let ["material", name] = tokens.as_slice() {
+                if materials.iter().any(|m| m.name == *name) {
+                    return error(&format!("material '{}' is defined twice", name));
+                }
+                materials.push(Material::new(name));
+                continue;
+            }
+            let material = match materials.last_mut() {
+                Some(material) if line.starts_with(char::is_whitespace) => material,
+                _ => return error("property must be indented under a material"),
+            };
+            let number = |token: &str| {
+                token
+                    .parse::<f32>()
+                    .map_err(|_| format!("line {}: invalid number '{}'", i + 1, token))
+            };
+            match tokens.as_slice() {
+                ["match", words @ ..] if !words.is_empty() => material
+                    .matches
+                    .extend(words.iter().map(|w| w.to_lowercase())),
+                ["layers", names @ ..] if !names.is_empty() => {
+                    for name in names {
+                        match layers::NAMES.iter().find(|(n, _)| n == name) {
+                            Some((_, layer)) => material.layers |= layer,
+                            None => return error(&format!("unknown layer '{}'", name)),
+                        }
+                    }
+                }
+                ["impact", effect] => material.impact_effect = effect.to_string(),
+                ["decal", texture, size] => {
+                    material.decal = Some((texture.to_string(), number(size)?.max(0.0)))
+                }
+                ["resistance", resistance] => material.resistance = number(resistance)?.max(0.0),
+                ["footstep", scale] => material.footstep_loudness = number(scale)?.max(0.0),
+                _ => return error(&format!("invalid property '{}'", trimmed)),
+            }
+        }
+        if materials.is_empty() {
+            return Err("there are no materials".to_owned());
+        }
+        Ok(Self { materials })
+    }
+
+    /// Material of a collider is chosen by its layer first, then by names of the node its
+    /// body is bound to and parents of that node. Colliders made in the editor are on every
+    /// layer, so only their nodes matter.
+    pub fn of_collider(&self, scene: &Scene, collider: ColliderHandle) -> &Material {
+        let collider = match scene.physics.colliders.get(collider.into()) {
+            Some(collider) => collider,
+            None => return &self.materials[0],
+        };
+        let membership = (collider.collision_groups().0 >> 16) as u16;
+        if membership != u16::MAX {
+            if let Some(material) = self.materials.iter().find(|m| m.layers & membership != 0) {
+                return material;
+            }
+        }
+        let mut node = scene
+            .physics_binder
+            .node_of(collider.parent().into())
+            .unwrap_or_default();
+        while node.is_some() {
+            let name = scene.graph[node].name().to_lowercase();
+            if let Some(material) = self
+                .materials
+                .iter()
+                .find(|m| m.matches.iter().any(|word| name.contains(word.as_str())))
+            {
+                return material;
+            }
+            node = scene.graph[node].parent();
+        }
+        &self.materials[0]
+    }
+
+    // Material of the surface right below the point, characters stand on it.
+    pub fn below(&self, scene: &Scene, point: Vector3<f32>) -> &Material {
+        let mut intersections = Vec::new();
+        scene.physics.cast_ray(
+            RayCastOptions {
+                ray: Ray::new(point, Vector3::new(0.0, -1.0, 0.0)),
+                max_len: 1.0,
+                groups: layers::query(layers::PROJECTILE, layers::WORLD | layers::PROP),
+                sort_results: true,
+            },
+            &mut intersections,
+        );
+        match intersections.first() {
+            Some(intersection) => self.of_collider(scene, intersection.collider),
+            None => &self.materials[0],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rg3d::{
+        core::pool::Handle,
+        physics::{dynamics::RigidBodyBuilder, geometry::ColliderBuilder},
+        scene::{base::BaseBuilder, node::Node},
+    };
+
+    const MATERIALS: &str = "\
+material concrete
+material wood
+    match Crate plank
+    decal wood.png 0.08
+    resistance 300
+    footstep 0.5
+material flesh
+    layers bot hitbox
+    impact blood
+";
+
+    fn parse_error(properties: &str) -> Option<String> {
+        Materials::parse(&format!("material test\n{}\n", properties)).err()
+    }
+
+    // Collider of a static body bound to a new node with the name.
+    fn add_collider(
+        scene: &mut Scene,
+        name: &str,
+        parent: Handle<Node>,
+        layer: Option<u16>,
+    ) -> (Handle<Node>, ColliderHandle) {
+        let node = BaseBuilder::new().with_name(name).build(&mut scene.graph);
+        if parent.is_some() {
+            scene.graph.link_nodes(node, parent);
+        }
+        let body = scene
+            .physics
+            .add_body(RigidBodyBuilder::new_static().build());
+        let mut collider = ColliderBuilder::ball(0.5);
+        if let Some(layer) = layer {
+            collider = collider.collision_groups(layers::collider(layer));
+        }
+        let collider = scene.physics.add_collider(collider.build(), body);
+        scene.physics_binder.bind(node, body);
+        (node, collider)
+    }
+
+    #[test]
+    fn valid_file_is_parsed() {
+        let materials = Materials::parse(MATERIALS).unwrap();
+        let names = materials.materials.iter().map(|m| m.name.as_str());
+        assert_eq!(names.collect::<Vec<_>>(), ["concrete", "wood", "flesh"]);
+        let wood = &materials.materials[1];
+        assert_eq!(wood.matches, ["crate", "plank"]);
+        assert_eq!(wood.decal, Some(("wood.png".to_owned(), 0.08)));
+        assert_eq!(wood.resistance, 300.0);
+        assert_eq!(wood.footstep_loudness, 0.5);
+        assert_eq!(wood.impact_effect, "impact");
+        let flesh = &materials.materials[2];
+        assert_eq!(flesh.layers, layers::BOT | layers::HITBOX);
+        assert_eq!(flesh.impact_effect, "blood");
+        assert_eq!(flesh.decal, None);
+    }
+
+    #[test]
+    fn shipped_materials_are_valid() {
+        assert!(Materials::load(crate::MATERIALS_PATH).is_ok());
+    }
+
+    #[test]
+    fn missing_values_are_rejected() {
+        assert_eq!(
+            parse_error("    decal wood.png"),
+            Some("line 2: invalid property 'decal wood.png'".to_owned())
+        );
+        assert_eq!(
+            parse_error("    match"),
+            Some("line 2: invalid property 'match'".to_owned())
+        );
+        assert_eq!(
+            Materials::parse("    footstep 2\n").err(),
+            Some("line 1: property must be indented under a material".to_owned())
+        );
+        assert_eq!(
+            Materials::parse("// Nothing here.\n").err(),
+            Some("there are no materials".to_owned())
+        );
+    }
+
+    #[test]
+    fn unknown_names_are_rejected() {
+        assert_eq!(
+            parse_error("    layers floor"),
+            Some("line 2: unknown layer 'floor'".to_owned())
+        );
+        assert_eq!(
+            parse_error("material test"),
+            Some("line 2: material 'test' is defined twice".to_owned())
+        );
+    }
+
+    #[test]
+    fn materials_are_found_by_layer_and_node_names() {
+        let materials = Materials::parse(MATERIALS).unwrap();
+        let mut scene = Scene::new();
+        let (crate_node, _) = add_collider(&mut scene, "Crate_01", Handle::NONE, None);
+        let (_, lid) = add_collider(&mut scene, "Lid", crate_node, None);
+        let (_, bot) = add_collider(&mut scene, "Crate_02", Handle::NONE, Some(layers::BOT));
+        let (_, rock) = add_collider(&mut scene, "Rock", Handle::NONE, None);
+        assert_eq!(materials.of_collider(&scene, lid).name, "wood");
+        // Layer wins over the name.
+        assert_eq!(materials.of_collider(&scene, bot).name, "flesh");
+        // Anything not matched is made of the first material.
+        assert_eq!(materials.of_collider(&scene, rock).name, "concrete");
+        assert_eq!(
+            materials.of_collider(&scene, Default::default()).name,
+            "concrete"
+        );
+    }
+}
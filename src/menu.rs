@@ -0,0 +1,173 @@
+use rg3d::{
+    core::{color::Color, pool::Handle},
+    gui::{
+        border::BorderBuilder,
+        brush::Brush,
+        button::ButtonBuilder,
+        grid::GridBuilder,
+        message::{ButtonMessage, MessageDirection, UiMessageData, WidgetMessage},
+        stack_panel::StackPanelBuilder,
+        text::TextBuilder,
+        widget::WidgetBuilder,
+        HorizontalAlignment, Thickness, VerticalAlignment,
+    },
+};
+
+use crate::{
+    state::{GameState, StateEvent},
+    Ui, UiMessage, UiNode,
+};
+
+const BUTTON_WIDTH: f32 = 200.0;
+const BUTTON_HEIGHT: f32 = 40.0;
+
+pub enum MenuAction {
+    Event(StateEvent),
+    Quit,
+}
+
+/// Main menu, loading screen, pause menu and game over screen. Every screen is a column of
+/// buttons in the middle of the screen over the dimmed scene.
+pub struct Menu {
+    root: Handle<UiNode>,
+    main_menu: Handle<UiNode>,
+    loading: Handle<UiNode>,
+    pause: Handle<UiNode>,
+    game_over: Handle<UiNode>,
+    // Every button with the action it triggers.
+    buttons: Vec<(Handle<UiNode>, MenuAction)>,
+}
+
+fn make_button(ui: &mut Ui, text: &str) -> Handle<UiNode> {
+    ButtonBuilder::new(
+        WidgetBuilder::new()
+            .with_width(BUTTON_WIDTH)
+            .with_height(BUTTON_HEIGHT)
+            .with_margin(Thickness::uniform(4.0)),
+    )
+    .with_text(text)
+    .build(&mut ui.build_ctx())
+}
+
+fn make_screen(ui: &mut Ui, title: &str, buttons: &[Handle<UiNode>]) -> Handle<UiNode> {
+    let ctx = &mut ui.build_ctx();
+    let title = TextBuilder::new(
+        WidgetBuilder::new()
+            .with_margin(Thickness::uniform(4.0))
+            .with_horizontal_alignment(HorizontalAlignment::Center),
+    )
+    .with_text(title)
+    .build(ctx);
+    StackPanelBuilder::new(
+        WidgetBuilder::new()
+            .with_visibility(false)
+            .with_horizontal_alignment(HorizontalAlignment::Center)
+            .with_vertical_alignment(VerticalAlignment::Center)
+            .with_child(title)
+            .with_children(buttons.iter()),
+    )
+    .build(ctx)
+}
+
+impl Menu {
+    pub fn new(ui: &mut Ui) -> Self {
+        let play = make_button(ui, "Play");
+        let quit = make_button(ui, "Quit");
+        let main_menu = make_screen(ui, "3D Shooter", &[play, quit]);
+
+        let loading = make_screen(ui, "Loading...", &[]);
+
+        let resume = make_button(ui, "Resume");
+        let pause_to_menu = make_button(ui, "Main Menu");
+        let pause_quit = make_button(ui, "Quit");
+        let pause = make_screen(ui, "Paused", &[resume, pause_to_menu, pause_quit]);
+
+        let restart = make_button(ui, "Restart");
+        let game_over_to_menu = make_button(ui, "Main Menu");
+        let game_over = make_screen(ui, "You died", &[restart, game_over_to_menu]);
+
+        let ctx = &mut ui.build_ctx();
+        let root = BorderBuilder::new(
+            WidgetBuilder::new()
+                .with_background(Brush::Solid(Color::from_rgba(0, 0, 0, 160)))
+                .with_child(
+                    GridBuilder::new(
+                        WidgetBuilder::new()
+                            .with_child(main_menu)
+                            .with_child(loading)
+                            .with_child(pause)
+                            .with_child(game_over),
+                    )
+                    .build(ctx),
+                ),
+        )
+        .build(ctx);
+
+        Self {
+            root,
+            main_menu,
+            loading,
+            pause,
+            game_over,
+            buttons: vec![
+                (play, MenuAction::Event(StateEvent::LoadLevel)),
+                (quit, MenuAction::Quit),
+                (resume, MenuAction::Event(StateEvent::Resume)),
+                (pause_to_menu, MenuAction::Event(StateEvent::ReturnToMenu)),
+                (pause_quit, MenuAction::Quit),
+                (restart, MenuAction::Event(StateEvent::LoadLevel)),
+                (
+                    game_over_to_menu,
+                    MenuAction::Event(StateEvent::ReturnToMenu),
+                ),
+            ],
+        }
+    }
+
+    pub fn resize(&self, ui: &Ui, width: f32, height: f32) {
+        ui.send_message(WidgetMessage::width(
+            self.root,
+            MessageDirection::ToWidget,
+            width,
+        ));
+        ui.send_message(WidgetMessage::height(
+            self.root,
+            MessageDirection::ToWidget,
+            height,
+        ));
+    }
+
+    // Shows the screen of given state, nothing is shown while playing.
+    pub fn set_state(&self, ui: &Ui, state: GameState) {
+        let screen = match state {
+            GameState::MainMenu => self.main_menu,
+            GameState::Loading => self.loading,
+            GameState::Paused => self.pause,
+            GameState::GameOver => self.game_over,
+            GameState::Playing => Handle::NONE,
+        };
+        for &node in [self.main_menu, self.loading, self.pause, self.game_over].iter() {
+            ui.send_message(WidgetMessage::visibility(
+                node,
+                MessageDirection::ToWidget,
+                node == screen,
+            ));
+        }
+        ui.send_message(WidgetMessage::visibility(
+            self.root,
+            MessageDirection::ToWidget,
+            screen.is_some(),
+        ));
+    }
+
+    pub fn handle_ui_message(&self, message: &UiMessage) -> Option<&MenuAction> {
+        if let UiMessageData::Button(ButtonMessage::Click) = message.data() {
+            self.buttons
+                .iter()
+                .find(|(button, _)| *button == message.destination())
+                .map(|(_, action)| action)
+        } else {
+            None
+        }
+    }
+}
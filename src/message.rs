@@ -1,7 +1,59 @@
-use rg3d::core::pool::Handle;
+use rg3d::{
+    core::{algebra::Vector3, pool::Handle},
+    scene::RigidBodyHandle,
+};
 
-use crate::weapon::Weapon;
+use crate::{hitbox::Region, weapon::Weapon};
 
 pub enum Message {
-    ShootWeapon { weapon: Handle<Weapon> },
+    ShootWeapon {
+        weapon: Handle<Weapon>,
+    },
+    ReloadWeapon {
+        weapon: Handle<Weapon>,
+    },
+    // Player pulls the pin of a grenade and throws it later.
+    CookGrenade,
+    ThrowGrenade,
+    Kick {
+        name: String,
+    },
+    ChangeMap {
+        name: String,
+    },
+    AddBot,
+    RemoveBot {
+        name: Option<String>,
+    },
+    ToggleGod,
+    ToggleNoclip,
+    Give {
+        item: String,
+        amount: Option<f32>,
+    },
+    SwitchWeapon {
+        name: String,
+    },
+    // Region is not known for damage which does not come from a hit.
+    DamagePlayer {
+        amount: f32,
+        region: Option<Region>,
+    },
+    DamageBody {
+        body: RigidBodyHandle,
+        amount: f32,
+    },
+    // Radius and damage are taken from cvars when not given.
+    Explode {
+        center: Vector3<f32>,
+        radius: Option<f32>,
+        damage: Option<f32>,
+    },
+    // Current weapon of the player is described when no name is given.
+    DumpFalloff {
+        name: Option<String>,
+    },
+    DumpPerception {
+        name: Option<String>,
+    },
 }
@@ -0,0 +1,663 @@
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BinaryHeap, HashMap},
+};
+
+use rg3d::{
+    core::{
+        algebra::{Point3, Vector3},
+        pool::Handle,
+        rand::Rng,
+    },
+    scene::{
+        graph::Graph,
+        mesh::Mesh,
+        node::Node,
+        physics::{BodyStatusDesc, Physics},
+        PhysicsBinder,
+    },
+};
+
+// Level may contain hand-made navigation mesh with this name, it is used instead of a
+// generated one.
+pub const NAVMESH_NAME: &str = "Navmesh";
+
+// Distance at which a point of a path is considered reached.
+const ARRIVE_DISTANCE: f32 = 0.25;
+
+// Distance between points checked on a straight line during path smoothing.
+const SMOOTHING_STEP: f32 = 0.25;
+
+// Size of cells of the lookup grid which is used to find a triangle under a point.
+const LOOKUP_CELL_SIZE: f32 = 1.0;
+
+// How deep below a point its triangle is searched. Agents are capsules, their positions are
+// somewhere above the ground.
+const SEARCH_DEPTH: f32 = 2.0;
+
+/// Parameters of navmesh generation, every size is in meters.
+pub struct NavmeshSettings {
+    pub cell_size: f32,
+    pub agent_radius: f32,
+    pub agent_height: f32,
+    // Steepest walkable slope in radians.
+    pub max_slope: f32,
+    // Highest ledge an agent can step on.
+    pub max_step: f32,
+}
+
+impl Default for NavmeshSettings {
+    fn default() -> Self {
+        Self {
+            cell_size: 0.5,
+            agent_radius: 0.2,
+            agent_height: 0.9,
+            max_slope: 45.0f32.to_radians(),
+            max_step: 0.3,
+        }
+    }
+}
+
+// Uniform grid over XZ plane, each cell knows every triangle that overlaps it.
+struct Lookup {
+    origin: (f32, f32),
+    width: usize,
+    depth: usize,
+    cells: Vec<Vec<usize>>,
+}
+
+impl Lookup {
+    fn new(vertices: &[Vector3<f32>], triangles: &[[u32; 3]]) -> Self {
+        let mut min = vertices.first().cloned().unwrap_or_default();
+        let mut max = min;
+        for vertex in vertices {
+            min = min.inf(vertex);
+            max = max.sup(vertex);
+        }
+        let width = ((max.x - min.x) / LOOKUP_CELL_SIZE).floor() as usize + 1;
+        let depth = ((max.z - min.z) / LOOKUP_CELL_SIZE).floor() as usize + 1;
+        let mut lookup = Self {
+            origin: (min.x, min.z),
+            width,
+            depth,
+            cells: vec![Vec::new(); width * depth],
+        };
+        for (index, triangle) in triangles.iter().enumerate() {
+            let points = triangle.iter().map(|&i| vertices[i as usize]);
+            let (t_min, t_max) = points.fold((max, min), |(a, b), p| (a.inf(&p), b.sup(&p)));
+            let (x0, z0) = lookup.cell(t_min.x, t_min.z);
+            let (x1, z1) = lookup.cell(t_max.x, t_max.z);
+            for z in z0..=z1 {
+                for x in x0..=x1 {
+                    lookup.cells[z * width + x].push(index);
+                }
+            }
+        }
+        lookup
+    }
+
+    fn cell(&self, x: f32, z: f32) -> (usize, usize) {
+        let cx = ((x - self.origin.0) / LOOKUP_CELL_SIZE).floor().max(0.0) as usize;
+        let cz = ((z - self.origin.1) / LOOKUP_CELL_SIZE).floor().max(0.0) as usize;
+        (cx.min(self.width - 1), cz.min(self.depth - 1))
+    }
+
+    fn triangles_at(&self, x: f32, z: f32) -> &[usize] {
+        let (cx, cz) = self.cell(x, z);
+        &self.cells[cz * self.width + cx]
+    }
+}
+
+// Entry of A* open set, the heap is a max-heap so the order is reversed.
+struct OpenNode {
+    cost: f32,
+    triangle: usize,
+}
+
+impl PartialEq for OpenNode {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost
+    }
+}
+
+impl Eq for OpenNode {}
+
+impl PartialOrd for OpenNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OpenNode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+/// Walkable surface of a level as a set of triangles. Paths are searched over triangles and
+/// go through middles of edges between them, so they never leave the mesh.
+pub struct Navmesh {
+    vertices: Vec<Vector3<f32>>,
+    triangles: Vec<[u32; 3]>,
+    // Triangles that share an edge with each triangle.
+    neighbours: Vec<Vec<usize>>,
+    max_step: f32,
+    lookup: Lookup,
+}
+
+impl Navmesh {
+    pub fn new(vertices: Vec<Vector3<f32>>, triangles: Vec<[u32; 3]>, max_step: f32) -> Self {
+        let mut edges = HashMap::<(u32, u32), Vec<usize>>::new();
+        for (index, triangle) in triangles.iter().enumerate() {
+            for i in 0..3 {
+                let (a, b) = (triangle[i], triangle[(i + 1) % 3]);
+                edges.entry((a.min(b), a.max(b))).or_default().push(index);
+            }
+        }
+        let mut neighbours = vec![Vec::new(); triangles.len()];
+        for shared in edges.values() {
+            for &a in shared {
+                neighbours[a].extend(shared.iter().filter(|&&b| b != a));
+            }
+        }
+        let lookup = Lookup::new(&vertices, &triangles);
+        Self {
+            vertices,
+            triangles,
+            neighbours,
+            max_step,
+            lookup,
+        }
+    }
+
+    // Hand-made navmesh may consist of several surfaces, so equal vertices are merged to
+    // connect triangles.
+    fn from_mesh(mesh: &Mesh, max_step: f32) -> Self {
+        let mut vertices = Vec::new();
+        let mut indices = HashMap::new();
+        let mut triangles = Vec::new();
+        for triangle in mesh_triangles(mesh) {
+            let mut definition = [0; 3];
+            for (index, point) in definition.iter_mut().zip(triangle.iter()) {
+                // Positions are compared with a millimeter precision.
+                let key = (
+                    (point.x * 1000.0).round() as i32,
+                    (point.y * 1000.0).round() as i32,
+                    (point.z * 1000.0).round() as i32,
+                );
+                *index = *indices.entry(key).or_insert_with(|| {
+                    vertices.push(*point);
+                    vertices.len() as u32 - 1
+                });
+            }
+            triangles.push(definition);
+        }
+        Self::new(vertices, triangles, max_step)
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.vertices.len()
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.triangles.len()
+    }
+
+    fn points(&self, triangle: usize) -> [Vector3<f32>; 3] {
+        let [a, b, c] = self.triangles[triangle];
+        [
+            self.vertices[a as usize],
+            self.vertices[b as usize],
+            self.vertices[c as usize],
+        ]
+    }
+
+    fn center(&self, triangle: usize) -> Vector3<f32> {
+        let [a, b, c] = self.points(triangle);
+        (a + b + c).scale(1.0 / 3.0)
+    }
+
+    /// Returns center of a random triangle, useful to pick a place to go.
+    pub fn random_point<R: Rng>(&self, rng: &mut R) -> Option<Vector3<f32>> {
+        if self.triangles.is_empty() {
+            None
+        } else {
+            Some(self.center(rng.gen_range(0..self.triangles.len())))
+        }
+    }
+
+    /// Edges which belong to a single triangle, walkable surface ends there. Every edge is
+    /// returned with the center of its triangle, so it is known which side is walkable.
+    pub fn boundary_edges(&self) -> Vec<([Vector3<f32>; 2], Vector3<f32>)> {
+        // Ordered map keeps the result the same from run to run.
+        let mut edges = BTreeMap::<(u32, u32), Vec<usize>>::new();
+        for (index, triangle) in self.triangles.iter().enumerate() {
+            for i in 0..3 {
+                let (a, b) = (triangle[i], triangle[(i + 1) % 3]);
+                edges.entry((a.min(b), a.max(b))).or_default().push(index);
+            }
+        }
+        edges
+            .into_iter()
+            .filter(|(_, triangles)| triangles.len() == 1)
+            .map(|((a, b), triangles)| {
+                (
+                    [self.vertices[a as usize], self.vertices[b as usize]],
+                    self.center(triangles[0]),
+                )
+            })
+            .collect()
+    }
+
+    // Finds the highest triangle right below given point (or slightly above it, within a
+    // step) and returns it with the point projected on it.
+    fn project(&self, point: Vector3<f32>) -> Option<(usize, Vector3<f32>)> {
+        if self.triangles.is_empty() {
+            return None;
+        }
+        let mut result: Option<(usize, Vector3<f32>)> = None;
+        for &triangle in self.lookup.triangles_at(point.x, point.z) {
+            if let Some(height) = height_at(&self.points(triangle), point.x, point.z) {
+                if height <= point.y + self.max_step
+                    && height >= point.y - SEARCH_DEPTH
+                    && result.is_none_or(|(_, p)| height > p.y)
+                {
+                    result = Some((triangle, Vector3::new(point.x, height, point.z)));
+                }
+            }
+        }
+        result
+    }
+
+    /// Finds a path between two points with A* over navmesh triangles and straightens it.
+    /// Returns `None` if either point is off the navmesh or there is no way between them.
+    pub fn find_path(&self, from: Vector3<f32>, to: Vector3<f32>) -> Option<Vec<Vector3<f32>>> {
+        let (start, from) = self.project(from)?;
+        let (goal, to) = self.project(to)?;
+
+        let mut parents = vec![usize::MAX; self.triangles.len()];
+        let mut costs = vec![f32::MAX; self.triangles.len()];
+        let mut open = BinaryHeap::new();
+        costs[start] = 0.0;
+        parents[start] = start;
+        open.push(OpenNode {
+            cost: (to - from).norm(),
+            triangle: start,
+        });
+        while let Some(OpenNode { triangle, .. }) = open.pop() {
+            if triangle == goal {
+                break;
+            }
+            let center = self.center(triangle);
+            for &neighbour in self.neighbours[triangle].iter() {
+                let neighbour_center = self.center(neighbour);
+                let cost = costs[triangle] + (neighbour_center - center).norm();
+                if cost < costs[neighbour] {
+                    costs[neighbour] = cost;
+                    parents[neighbour] = triangle;
+                    open.push(OpenNode {
+                        cost: cost + (to - neighbour_center).norm(),
+                        triangle: neighbour,
+                    });
+                }
+            }
+        }
+        if parents[goal] == usize::MAX {
+            return None;
+        }
+
+        // Walk back from the goal, the path goes through middles of shared edges.
+        let mut path = vec![to];
+        let mut current = goal;
+        while current != start {
+            let parent = parents[current];
+            path.push(self.shared_edge_middle(current, parent));
+            current = parent;
+        }
+        path.push(from);
+        path.reverse();
+        Some(self.smooth_path(&path))
+    }
+
+    fn shared_edge_middle(&self, a: usize, b: usize) -> Vector3<f32> {
+        let shared = self.triangles[a]
+            .iter()
+            .filter(|i| self.triangles[b].contains(i))
+            .map(|&i| self.vertices[i as usize])
+            .collect::<Vec<_>>();
+        shared
+            .iter()
+            .sum::<Vector3<f32>>()
+            .scale(1.0 / shared.len() as f32)
+    }
+
+    // Checks that an agent can walk from one point to another in a straight line, that is
+    // every point of the line has ground under it and there are no ledges on the way.
+    fn is_straight_walkable(&self, from: Vector3<f32>, to: Vector3<f32>) -> bool {
+        let steps = ((to - from).norm() / SMOOTHING_STEP).ceil().max(1.0) as usize;
+        let mut previous = from;
+        for i in 1..=steps {
+            let mut point = from.lerp(&to, i as f32 / steps as f32);
+            point.y = previous.y;
+            match self.project(point) {
+                Some((_, ground)) if (ground.y - previous.y).abs() <= self.max_step => {
+                    previous = ground
+                }
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    // Removes every point which can be skipped by walking in a straight line.
+    fn smooth_path(&self, path: &[Vector3<f32>]) -> Vec<Vector3<f32>> {
+        let mut result = path.first().cloned().into_iter().collect::<Vec<_>>();
+        let mut anchor = 0;
+        while anchor + 1 < path.len() {
+            let last = path.len() - 1;
+            // Most paths in open areas are straight lines, check that first.
+            let mut next = if self.is_straight_walkable(path[anchor], path[last]) {
+                last
+            } else {
+                anchor + 1
+            };
+            while next + 1 < path.len() && self.is_straight_walkable(path[anchor], path[next + 1]) {
+                next += 1;
+            }
+            result.push(path[next]);
+            anchor = next;
+        }
+        result
+    }
+}
+
+// World space triangles of every surface of a mesh.
+fn mesh_triangles(mesh: &Mesh) -> Vec<[Vector3<f32>; 3]> {
+    let transform = mesh.global_transform();
+    let mut triangles = Vec::new();
+    for surface in mesh.surfaces() {
+        let data = surface.data();
+        let data = data.read().unwrap();
+        let vertices = data.get_vertices();
+        for triangle in data.triangles() {
+            let point = |i: usize| {
+                transform
+                    .transform_point(&Point3::from(vertices[triangle[i] as usize].position))
+                    .coords
+            };
+            triangles.push([point(0), point(1), point(2)]);
+        }
+    }
+    triangles
+}
+
+/// Creates navmesh of a level. Mesh named `NAVMESH_NAME` is used as is (and hidden), otherwise
+/// navmesh is generated from static geometry, `is_static` tells which nodes never move.
+pub fn build_navmesh<F>(graph: &mut Graph, settings: &NavmeshSettings, is_static: F) -> Navmesh
+where
+    F: Fn(Handle<Node>) -> bool,
+{
+    let authored = graph.find_by_name_from_root(NAVMESH_NAME);
+    if authored.is_some() {
+        if let Node::Mesh(mesh) = &graph[authored] {
+            let navmesh = Navmesh::from_mesh(mesh, settings.max_step);
+            graph[authored].set_visibility(false);
+            return navmesh;
+        }
+    }
+
+    generate(&static_triangles(graph, is_static), settings)
+}
+
+/// Tells whether a node is a part of static geometry, that is it is not bound to a dynamic
+/// body. Physics of a scene loaded without the engine is not restored, only descriptors of
+/// its bodies are there, so they are checked instead.
+pub fn is_static_node(physics: &Physics, binder: &PhysicsBinder, node: Handle<Node>) -> bool {
+    let body = match binder.forward_map().get(&node) {
+        Some(body) => *body,
+        None => return true,
+    };
+    match physics.desc.as_ref() {
+        // Bodies are restored in the order of their descriptors.
+        Some(desc) => desc
+            .bodies
+            .get(body.0.into_raw_parts().0)
+            .is_none_or(|body| !matches!(body.status, BodyStatusDesc::Dynamic)),
+        None => physics
+            .bodies
+            .get(body.into())
+            .is_none_or(|body| !body.is_dynamic()),
+    }
+}
+
+// World space triangles of every mesh that never moves. Mesh moves together with its
+// ancestors, so all of them must be static.
+pub fn static_triangles<F>(graph: &Graph, is_static: F) -> Vec<[Vector3<f32>; 3]>
+where
+    F: Fn(Handle<Node>) -> bool,
+{
+    let never_moves = |mut handle: Handle<Node>| {
+        while handle.is_some() {
+            if !is_static(handle) {
+                return false;
+            }
+            handle = graph[handle].parent();
+        }
+        true
+    };
+    let mut triangles = Vec::new();
+    for (handle, node) in graph.pair_iter() {
+        if let Node::Mesh(mesh) = node {
+            if never_moves(handle) {
+                triangles.extend(mesh_triangles(mesh));
+            }
+        }
+    }
+    triangles
+}
+
+// Walkable surfaces are rasterized into a grid of cells, each cell is either walkable with
+// some floor height or blocked. Every walkable cell then becomes a quad of the navmesh.
+fn generate(triangles: &[[Vector3<f32>; 3]], settings: &NavmeshSettings) -> Navmesh {
+    let min_normal_y = settings.max_slope.cos();
+    // Triangles are clockwise, so that is the way to get normals looking outside.
+    let (walkable, obstacles): (Vec<&[Vector3<f32>; 3]>, Vec<_>) =
+        triangles.iter().partition(|t| {
+            (t[2] - t[0])
+                .cross(&(t[1] - t[0]))
+                .try_normalize(f32::EPSILON)
+                .is_some_and(|n| n.y >= min_normal_y)
+        });
+    if walkable.is_empty() {
+        return Navmesh::new(Vec::new(), Vec::new(), settings.max_step);
+    }
+
+    let (mut min, mut max) = (walkable[0][0], walkable[0][0]);
+    for point in walkable.iter().flat_map(|t| t.iter()) {
+        min = min.inf(point);
+        max = max.sup(point);
+    }
+    let cell_size = settings.cell_size;
+    let width = ((max.x - min.x) / cell_size).ceil().max(1.0) as usize;
+    let depth = ((max.z - min.z) / cell_size).ceil().max(1.0) as usize;
+    let cell_range = |from: f32, to: f32, origin: f32, count: usize| {
+        let first = ((from - origin) / cell_size).floor().max(0.0) as usize;
+        let last = (((to - origin) / cell_size).ceil().max(0.0) as usize).min(count);
+        first..last
+    };
+
+    // Floor height of a cell is the highest walkable surface at its center.
+    let mut heights: Vec<Option<f32>> = vec![None; width * depth];
+    for triangle in walkable.iter() {
+        let (t_min, t_max) = triangle_bounds(triangle);
+        for z in cell_range(t_min.z, t_max.z, min.z, depth) {
+            for x in cell_range(t_min.x, t_max.x, min.x, width) {
+                let center_x = min.x + (x as f32 + 0.5) * cell_size;
+                let center_z = min.z + (z as f32 + 0.5) * cell_size;
+                if let Some(height) = height_at(triangle, center_x, center_z) {
+                    let cell = &mut heights[z * width + x];
+                    *cell = Some(cell.map_or(height, |h| h.max(height)));
+                }
+            }
+        }
+    }
+
+    // Cell is blocked if there is something between the step height and the head of an
+    // agent standing on it. Bounds of a triangle are used instead of the triangle itself,
+    // it is conservative but simple.
+    for triangle in obstacles.iter() {
+        let (t_min, t_max) = triangle_bounds(triangle);
+        let radius = settings.agent_radius;
+        for z in cell_range(t_min.z - radius, t_max.z + radius, min.z, depth) {
+            for x in cell_range(t_min.x - radius, t_max.x + radius, min.x, width) {
+                let cell = &mut heights[z * width + x];
+                if let Some(height) = *cell {
+                    if t_max.y > height + settings.max_step
+                        && t_min.y < height + settings.agent_height
+                    {
+                        *cell = None;
+                    }
+                }
+            }
+        }
+    }
+
+    // Neighbour cells share corner vertices if their heights are within a step. Several
+    // vertices may be at the same corner, the closest one in height is shared.
+    let mut vertices: Vec<Vector3<f32>> = Vec::new();
+    let mut corners = HashMap::<(usize, usize), Vec<u32>>::new();
+    let mut navmesh_triangles = Vec::new();
+    for z in 0..depth {
+        for x in 0..width {
+            if let Some(height) = heights[z * width + x] {
+                let mut corner = |cx: usize, cz: usize| {
+                    let shared = corners.entry((cx, cz)).or_default();
+                    let closest = shared
+                        .iter()
+                        .map(|&index| (index, (vertices[index as usize].y - height).abs()))
+                        .filter(|(_, difference)| *difference <= settings.max_step)
+                        .min_by(|a, b| a.1.total_cmp(&b.1));
+                    match closest {
+                        Some((index, _)) => index,
+                        None => {
+                            vertices.push(Vector3::new(
+                                min.x + cx as f32 * cell_size,
+                                height,
+                                min.z + cz as f32 * cell_size,
+                            ));
+                            shared.push(vertices.len() as u32 - 1);
+                            vertices.len() as u32 - 1
+                        }
+                    }
+                };
+                let a = corner(x, z);
+                let b = corner(x + 1, z);
+                let c = corner(x + 1, z + 1);
+                let d = corner(x, z + 1);
+                navmesh_triangles.push([a, b, c]);
+                navmesh_triangles.push([a, c, d]);
+            }
+        }
+    }
+    Navmesh::new(vertices, navmesh_triangles, settings.max_step)
+}
+
+pub fn triangle_bounds(triangle: &[Vector3<f32>; 3]) -> (Vector3<f32>, Vector3<f32>) {
+    (
+        triangle[0].inf(&triangle[1]).inf(&triangle[2]),
+        triangle[0].sup(&triangle[1]).sup(&triangle[2]),
+    )
+}
+
+// Height of a triangle at given point of XZ plane, if the point is inside of the triangle.
+fn height_at(triangle: &[Vector3<f32>; 3], x: f32, z: f32) -> Option<f32> {
+    // Points on shared edges must belong to both triangles.
+    const EPSILON: f32 = 1.0e-4;
+
+    let [a, b, c] = triangle;
+    let det = (b.z - c.z) * (a.x - c.x) + (c.x - b.x) * (a.z - c.z);
+    if det.abs() < f32::EPSILON {
+        return None;
+    }
+    let u = ((b.z - c.z) * (x - c.x) + (c.x - b.x) * (z - c.z)) / det;
+    let v = ((c.z - a.z) * (x - c.x) + (a.x - c.x) * (z - c.z)) / det;
+    let w = 1.0 - u - v;
+    if u >= -EPSILON && v >= -EPSILON && w >= -EPSILON {
+        Some(u * a.y + v * b.y + w * c.y)
+    } else {
+        None
+    }
+}
+
+/// Moves an agent along a path, the agent itself is driven by velocity it returns.
+#[derive(Default)]
+pub struct PathFollower {
+    path: Vec<Vector3<f32>>,
+    current: usize,
+}
+
+impl PathFollower {
+    pub fn set_path(&mut self, path: Vec<Vector3<f32>>) {
+        self.path = path;
+        self.current = 0;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.current >= self.path.len()
+    }
+
+    // Last point of the current path, it is kept after the path is finished.
+    pub fn destination(&self) -> Option<Vector3<f32>> {
+        self.path.last().copied()
+    }
+
+    // Returns horizontal velocity toward the next point of the path, zero when it is finished.
+    pub fn steer(&mut self, position: Vector3<f32>, speed: f32) -> Vector3<f32> {
+        while let Some(point) = self.path.get(self.current) {
+            let delta = Vector3::new(point.x - position.x, 0.0, point.z - position.z);
+            if delta.norm() > ARRIVE_DISTANCE {
+                return delta.normalize().scale(speed);
+            }
+            self.current += 1;
+        }
+        Vector3::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Floor quad made of two clockwise triangles.
+    fn floor(x: f32, width: f32, height: f32) -> [[Vector3<f32>; 3]; 2] {
+        let corner = |dx: f32, z: f32| Vector3::new(x + dx, height, z);
+        [
+            [corner(0.0, 0.0), corner(width, 0.0), corner(0.0, 1.0)],
+            [corner(width, 0.0), corner(width, 1.0), corner(0.0, 1.0)],
+        ]
+    }
+
+    #[test]
+    fn floors_within_step_are_connected() {
+        let settings = NavmeshSettings::default();
+        // Heights are on both sides of the half of a step.
+        let half = settings.max_step * 0.5;
+        let mut triangles = floor(0.0, 2.0, half - 0.025).to_vec();
+        triangles.extend_from_slice(&floor(2.0, 2.0, half + 0.025));
+        let navmesh = generate(&triangles, &settings);
+        let to = Vector3::new(3.5, 0.5, 0.5);
+        let path = navmesh.find_path(Vector3::new(0.5, 0.5, 0.5), to).unwrap();
+        assert!((path.last().unwrap().xz() - to.xz()).norm() < 0.01);
+    }
+
+    #[test]
+    fn floors_higher_than_step_are_not_connected() {
+        let settings = NavmeshSettings::default();
+        let mut triangles = floor(0.0, 2.0, 0.0).to_vec();
+        triangles.extend_from_slice(&floor(2.0, 2.0, settings.max_step * 1.5));
+        let navmesh = generate(&triangles, &settings);
+        assert!(navmesh
+            .find_path(Vector3::new(0.5, 1.0, 0.5), Vector3::new(3.5, 1.0, 0.5))
+            .is_none());
+    }
+}
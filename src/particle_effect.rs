@@ -0,0 +1,512 @@
+use std::{
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use rg3d::{
+    core::{
+        algebra::Vector3,
+        color::Color,
+        color_gradient::{ColorGradient, GradientPoint},
+        numeric_range::NumericRange,
+    },
+    engine::resource_manager::ResourceManager,
+    resource::texture::Texture,
+    scene::particle_system::{
+        BaseEmitterBuilder, BoxEmitterBuilder, CylinderEmitterBuilder, Emitter, ParticleSystem,
+        SphereEmitterBuilder,
+    },
+};
+
+// Effects files are checked for changes that often.
+const HOT_RELOAD_INTERVAL: f32 = 1.0;
+
+enum Shape {
+    Sphere { radius: f32 },
+    Box { width: f32, height: f32, depth: f32 },
+    Cylinder { radius: f32, height: f32 },
+}
+
+// Every value which is not set keeps the engine's default.
+struct EmitterDefinition {
+    shape: Shape,
+    position: Vector3<f32>,
+    max_particles: Option<u32>,
+    spawn_rate: Option<u32>,
+    lifetime: Option<NumericRange>,
+    size: Option<NumericRange>,
+    size_modifier: Option<NumericRange>,
+    x_velocity: Option<NumericRange>,
+    y_velocity: Option<NumericRange>,
+    z_velocity: Option<NumericRange>,
+    rotation_speed: Option<NumericRange>,
+    rotation: Option<NumericRange>,
+    resurrect: bool,
+}
+
+impl EmitterDefinition {
+    fn new(shape: Shape) -> Self {
+        Self {
+            shape,
+            position: Vector3::default(),
+            max_particles: None,
+            spawn_rate: None,
+            lifetime: None,
+            size: None,
+            size_modifier: None,
+            x_velocity: None,
+            y_velocity: None,
+            z_velocity: None,
+            rotation_speed: None,
+            rotation: None,
+            resurrect: false,
+        }
+    }
+
+    // Builds a fresh emitter which has not emitted anything yet.
+    fn build(&self) -> Emitter {
+        let mut base = BaseEmitterBuilder::new()
+            .with_position(self.position)
+            .resurrect_particles(self.resurrect);
+        if let Some(max_particles) = self.max_particles {
+            base = base.with_max_particles(max_particles);
+        }
+        if let Some(spawn_rate) = self.spawn_rate {
+            // Engine breaks if an emitter spawns more than its limit of particles in a single
+            // frame, so the limit is never reached faster than in a few frames.
+            let max_rate = self
+                .max_particles
+                .map_or(u32::MAX, |max| max.saturating_mul(20));
+            base = base.with_spawn_rate(spawn_rate.min(max_rate));
+        }
+        if let Some(range) = self.lifetime {
+            base = base.with_lifetime_range(range);
+        }
+        if let Some(range) = self.size {
+            base = base.with_size_range(range);
+        }
+        if let Some(range) = self.size_modifier {
+            base = base.with_size_modifier_range(range);
+        }
+        if let Some(range) = self.x_velocity {
+            base = base.with_x_velocity_range(range);
+        }
+        if let Some(range) = self.y_velocity {
+            base = base.with_y_velocity_range(range);
+        }
+        if let Some(range) = self.z_velocity {
+            base = base.with_z_velocity_range(range);
+        }
+        if let Some(range) = self.rotation_speed {
+            base = base.with_rotation_speed_range(range);
+        }
+        if let Some(range) = self.rotation {
+            base = base.with_rotation_range(range);
+        }
+        match self.shape {
+            Shape::Sphere { radius } => SphereEmitterBuilder::new(base).with_radius(radius).build(),
+            Shape::Box {
+                width,
+                height,
+                depth,
+            } => BoxEmitterBuilder::new(base)
+                .with_width(width)
+                .with_height(height)
+                .with_depth(depth)
+                .build(),
+            Shape::Cylinder { radius, height } => CylinderEmitterBuilder::new(base)
+                .with_radius(radius)
+                .with_height(height)
+                .build(),
+        }
+    }
+}
+
+/// Particle system described by an effect file.
+pub struct ParticleEffect {
+    // How long the effect is shown, every particle should be gone by then.
+    pub lifetime: f32,
+    acceleration: Vector3<f32>,
+    texture_path: Option<String>,
+    texture: Option<Texture>,
+    gradient: Option<ColorGradient>,
+    emitters: Vec<EmitterDefinition>,
+}
+
+impl Default for ParticleEffect {
+    fn default() -> Self {
+        Self {
+            lifetime: 1.0,
+            acceleration: Vector3::default(),
+            texture_path: None,
+            texture: None,
+            gradient: None,
+            emitters: Vec::new(),
+        }
+    }
+}
+
+impl ParticleEffect {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut effect = Self::default();
+        // Block the indented lines belong to.
+        let mut block = None;
+        for (i, line) in text.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with("//") || trimmed.starts_with('#') {
+                continue;
+            }
+            let error = |text: &str| Err(format!("line {}: {}", i + 1, text));
+            let number = |token: &str| {
+                token
+                    .parse::<f32>()
+                    .map_err(|_| format!("line {}: invalid number '{}'", i + 1, token))
+            };
+            let count = |token: &str| {
+                token
+                    .parse::<u32>()
+                    .map_err(|_| format!("line {}: invalid count '{}'", i + 1, token))
+            };
+            let range = |min: &str, max: &str| -> Result<_, String> {
+                Ok(Some(NumericRange::new(number(min)?, number(max)?)))
+            };
+            let tokens = trimmed.split_whitespace().collect::<Vec<_>>();
+
+            if !line.starts_with(char::is_whitespace) {
+                block = None;
+                match tokens.as_slice() {
+                    ["lifetime", time] => effect.lifetime = number(time)?.max(0.0),
+                    ["acceleration", x, y, z] => {
+                        effect.acceleration = Vector3::new(number(x)?, number(y)?, number(z)?)
+                    }
+                    ["texture", path] => effect.texture_path = Some(path.to_string()),
+                    ["gradient"] => {
+                        if effect.gradient.is_some() {
+                            return error("gradient is defined twice");
+                        }
+                        effect.gradient = Some(ColorGradient::new());
+                        block = Some("gradient");
+                    }
+                    ["emitter", shape @ ..] => {
+                        let shape = match shape {
+                            ["sphere", radius] => Shape::Sphere {
+                                radius: number(radius)?,
+                            },
+                            ["box", width, height, depth] => Shape::Box {
+                                width: number(width)?,
+                                height: number(height)?,
+                                depth: number(depth)?,
+                            },
+                            ["cylinder", radius, height] => Shape::Cylinder {
+                                radius: number(radius)?,
+                                height: number(height)?,
+                            },
+                            _ => return error(&format!("invalid emitter '{}'", trimmed)),
+                        };
+                        effect.emitters.push(EmitterDefinition::new(shape));
+                        block = Some("emitter");
+                    }
+                    _ => return error(&format!("invalid property '{}'", trimmed)),
+                }
+                continue;
+            }
+
+            match block {
+                Some("gradient") => {
+                    let color = |token: &str| {
+                        token
+                            .parse::<u8>()
+                            .map_err(|_| format!("line {}: invalid color '{}'", i + 1, token))
+                    };
+                    let (location, color) = match tokens.as_slice() {
+                        [location, r, g, b, a] => (
+                            number(location)?,
+                            Color::from_rgba(color(r)?, color(g)?, color(b)?, color(a)?),
+                        ),
+                        _ => return error("gradient point must be <location> <r> <g> <b> <a>"),
+                    };
+                    if !(0.0..=1.0).contains(&location) {
+                        return error("gradient point location must be between 0 and 1");
+                    }
+                    let gradient = effect.gradient.as_mut().unwrap();
+                    gradient.add_point(GradientPoint::new(location, color));
+                }
+                Some("emitter") => {
+                    let emitter = effect.emitters.last_mut().unwrap();
+                    match tokens.as_slice() {
+                        ["position", x, y, z] => {
+                            emitter.position = Vector3::new(number(x)?, number(y)?, number(z)?)
+                        }
+                        ["particles", amount] => emitter.max_particles = Some(count(amount)?),
+                        ["rate", rate] => emitter.spawn_rate = Some(count(rate)?),
+                        ["lifetime", min, max] => emitter.lifetime = range(min, max)?,
+                        ["size", min, max] => emitter.size = range(min, max)?,
+                        ["size_change", min, max] => emitter.size_modifier = range(min, max)?,
+                        ["velocity_x", min, max] => emitter.x_velocity = range(min, max)?,
+                        ["velocity_y", min, max] => emitter.y_velocity = range(min, max)?,
+                        ["velocity_z", min, max] => emitter.z_velocity = range(min, max)?,
+                        ["rotation", min, max] => emitter.rotation = range(min, max)?,
+                        ["rotation_speed", min, max] => emitter.rotation_speed = range(min, max)?,
+                        ["resurrect"] => emitter.resurrect = true,
+                        _ => return error(&format!("invalid emitter property '{}'", trimmed)),
+                    }
+                }
+                _ => return error("property must be indented under an emitter or a gradient"),
+            }
+        }
+        if effect.emitters.is_empty() {
+            return Err("there are no emitters".to_owned());
+        }
+        Ok(effect)
+    }
+
+    /// Sets up a particle system to play the effect from the start. Nothing is allocated if
+    /// the particle system already played the same effect before.
+    pub fn apply(&self, particle_system: &mut ParticleSystem) {
+        particle_system.clear_particles();
+        // Emitters have to be replaced, they do not emit anything once they are out of
+        // particles.
+        particle_system.emitters.truncate(self.emitters.len());
+        for (i, definition) in self.emitters.iter().enumerate() {
+            match particle_system.emitters.get_mut(i) {
+                Some(emitter) => *emitter = definition.build(),
+                None => particle_system.emitters.push(definition.build()),
+            }
+        }
+        particle_system.set_acceleration(self.acceleration);
+        particle_system.set_texture(self.texture.clone());
+    }
+
+    // Gradient is set separately, it has to be copied every time.
+    pub fn apply_gradient(&self, particle_system: &mut ParticleSystem) {
+        if let Some(gradient) = self.gradient.as_ref() {
+            particle_system.set_color_over_lifetime_gradient(gradient.clone());
+        }
+    }
+}
+
+struct Entry {
+    name: String,
+    modified: Option<SystemTime>,
+    // Effect whose file could not be loaded yet is missing.
+    effect: Option<ParticleEffect>,
+}
+
+/// Every effect from the effects directory, each file is an effect named as the file without
+/// extension. Files are reloaded when they change while the game runs.
+#[derive(Default)]
+pub struct ParticleEffects {
+    directory: PathBuf,
+    entries: Vec<Entry>,
+    resource_manager: Option<ResourceManager>,
+    reload_timer: f32,
+    // Incremented on every reload, so users of effects know they have to be set up again.
+    generation: u32,
+}
+
+impl ParticleEffects {
+    /// Loads every effect from the directory. Textures are not loaded without a resource
+    /// manager. Effects that cannot be loaded are reported and skipped.
+    pub fn load<P: AsRef<Path>>(
+        directory: P,
+        resource_manager: Option<ResourceManager>,
+    ) -> Result<Self, String> {
+        let mut effects = Self {
+            directory: directory.as_ref().to_owned(),
+            resource_manager,
+            ..Default::default()
+        };
+        for line in effects.scan()? {
+            println!("{}", line);
+        }
+        Ok(effects)
+    }
+
+    /// Index of an effect by its name, it never changes while the game runs.
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.entries
+            .iter()
+            .position(|entry| entry.name == name && entry.effect.is_some())
+    }
+
+    pub fn get(&self, index: usize) -> &ParticleEffect {
+        self.entries[index].effect.as_ref().unwrap()
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    /// Reloads effects whose files have changed and loads new ones. Returns what happened,
+    /// to be shown in the console. The previous version of a broken effect is kept.
+    pub fn update(&mut self, dt: f32) -> Vec<String> {
+        self.reload_timer -= dt;
+        if self.reload_timer > 0.0 {
+            return Vec::new();
+        }
+        self.reload_timer = HOT_RELOAD_INTERVAL;
+        self.scan()
+            .unwrap_or_else(|e| vec![format!("Unable to reload effects. Reason: {}", e)])
+    }
+
+    // Loads files which are new or modified since the last scan, returns a line for every
+    // reloaded effect and every error.
+    fn scan(&mut self) -> Result<Vec<String>, String> {
+        let directory = std::fs::read_dir(&self.directory)
+            .map_err(|e| format!("unable to read {}: {}", self.directory.display(), e))?;
+        let mut report = Vec::new();
+        for file in directory.flatten() {
+            let path = file.path();
+            let name = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(name) if path.extension() == Some("txt".as_ref()) => name,
+                _ => continue,
+            };
+            let modified = file.metadata().and_then(|m| m.modified()).ok();
+            let index = self.entries.iter().position(|entry| entry.name == name);
+            if let Some(index) = index {
+                if modified.is_some() && self.entries[index].modified == modified {
+                    continue;
+                }
+            }
+            let effect = std::fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|text| ParticleEffect::parse(&text));
+            let effect = match effect {
+                Ok(mut effect) => {
+                    effect.texture = match (&effect.texture_path, &self.resource_manager) {
+                        (Some(texture), Some(resource_manager)) => {
+                            Some(resource_manager.request_texture(Path::new(texture)))
+                        }
+                        _ => None,
+                    };
+                    Some(effect)
+                }
+                // Broken file is not read again until it changes, the previous version of
+                // the effect stays.
+                Err(e) => {
+                    report.push(format!(
+                        "Unable to load effect. Reason: {}: {}",
+                        path.display(),
+                        e
+                    ));
+                    None
+                }
+            };
+            match index {
+                Some(index) => {
+                    let entry = &mut self.entries[index];
+                    entry.modified = modified;
+                    if effect.is_some() {
+                        entry.effect = effect;
+                        report.push(format!("Effect {} is reloaded", entry.name));
+                        self.generation += 1;
+                    }
+                }
+                None => self.entries.push(Entry {
+                    name: name.to_owned(),
+                    modified,
+                    effect,
+                }),
+            }
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EFFECT: &str = "\
+lifetime 0.5
+acceleration 0 -9.8 0
+texture spark.png
+gradient
+    0 255 255 0 0
+    1 255 0 0 255
+emitter sphere 0.1
+    particles 20
+    rate 100
+    lifetime 0.2 0.4
+emitter box 1 2 3
+    position 0 1 0
+    resurrect
+";
+
+    fn parse_error(text: &str) -> Option<String> {
+        ParticleEffect::parse(text).err()
+    }
+
+    #[test]
+    fn valid_effect_is_parsed() {
+        let effect = ParticleEffect::parse(EFFECT).unwrap();
+        assert_eq!(effect.lifetime, 0.5);
+        assert_eq!(effect.acceleration, Vector3::new(0.0, -9.8, 0.0));
+        assert_eq!(effect.texture_path.as_deref(), Some("spark.png"));
+        assert!(effect.gradient.is_some());
+        assert_eq!(effect.emitters.len(), 2);
+        let sphere = &effect.emitters[0];
+        assert!(matches!(sphere.shape, Shape::Sphere { radius } if radius == 0.1));
+        assert_eq!(sphere.max_particles, Some(20));
+        assert_eq!(sphere.spawn_rate, Some(100));
+        assert!(!sphere.resurrect);
+        let cube = &effect.emitters[1];
+        assert!(matches!(cube.shape, Shape::Box { depth, .. } if depth == 3.0));
+        assert_eq!(cube.position, Vector3::new(0.0, 1.0, 0.0));
+        assert!(cube.resurrect);
+    }
+
+    #[test]
+    fn shipped_effects_are_valid() {
+        for file in std::fs::read_dir(crate::EFFECTS_PATH).unwrap().flatten() {
+            let text = std::fs::read_to_string(file.path()).unwrap();
+            if let Err(e) = ParticleEffect::parse(&text) {
+                panic!("{}: {}", file.path().display(), e);
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_effects_are_rejected() {
+        assert_eq!(
+            parse_error("lifetime 1\n"),
+            Some("there are no emitters".to_owned())
+        );
+        assert_eq!(
+            parse_error("emitter cone 1\n"),
+            Some("line 1: invalid emitter 'emitter cone 1'".to_owned())
+        );
+        assert_eq!(
+            parse_error("emitter sphere 1\n    speed 2\n"),
+            Some("line 2: invalid emitter property 'speed 2'".to_owned())
+        );
+        assert_eq!(
+            parse_error("    particles 20\nemitter sphere 1\n"),
+            Some("line 1: property must be indented under an emitter or a gradient".to_owned())
+        );
+        assert_eq!(
+            parse_error("emitter sphere 1\n    particles many\n"),
+            Some("line 2: invalid count 'many'".to_owned())
+        );
+    }
+
+    #[test]
+    fn invalid_gradients_are_rejected() {
+        assert_eq!(
+            parse_error("gradient\n    1.5 255 0 0 255\n"),
+            Some("line 2: gradient point location must be between 0 and 1".to_owned())
+        );
+        assert_eq!(
+            parse_error("gradient\n    0 256 0 0 255\n"),
+            Some("line 2: invalid color '256'".to_owned())
+        );
+        assert_eq!(
+            parse_error("gradient\n    0 255 0 0\n"),
+            Some("line 2: gradient point must be <location> <r> <g> <b> <a>".to_owned())
+        );
+        assert_eq!(
+            parse_error("gradient\ngradient\n"),
+            Some("line 2: gradient is defined twice".to_owned())
+        );
+    }
+}
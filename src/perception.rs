@@ -0,0 +1,191 @@
+use rg3d::{
+    core::{algebra::Vector3, math::ray::Ray},
+    scene::{
+        physics::{Physics, RayCastOptions},
+        ColliderHandle,
+    },
+};
+
+use crate::layers;
+
+// Time in seconds during which a bot remembers where it noticed an enemy last time.
+const MEMORY_TIME: f32 = 10.0;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum NoiseKind {
+    Gunshot,
+    Footstep,
+}
+
+/// Something that can be heard by bots within given radius. Noises live for a single tick.
+pub struct Noise {
+    pub position: Vector3<f32>,
+    pub radius: f32,
+    pub kind: NoiseKind,
+    // Collider of whoever made the noise.
+    pub emitter: ColliderHandle,
+}
+
+/// Anything a bot is looking for.
+pub struct Target {
+    pub position: Vector3<f32>,
+    pub collider: ColliderHandle,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Sense {
+    Sight,
+    Hearing(NoiseKind),
+}
+
+/// Last known position of an enemy and the way it was noticed.
+pub struct Memory {
+    pub position: Vector3<f32>,
+    pub sense: Sense,
+    // Time in seconds since the enemy was noticed.
+    pub age: f32,
+}
+
+/// What a bot sees, hears and remembers.
+pub struct Perception {
+    // Full angle of the vision cone in radians.
+    pub fov: f32,
+    pub view_distance: f32,
+    visible_target: Option<Vector3<f32>>,
+    memory: Option<Memory>,
+}
+
+impl Perception {
+    pub fn new(fov: f32, view_distance: f32) -> Self {
+        Self {
+            fov,
+            view_distance,
+            visible_target: None,
+            memory: None,
+        }
+    }
+
+    // Position of the closest enemy a bot sees right now.
+    pub fn visible_target(&self) -> Option<Vector3<f32>> {
+        self.visible_target
+    }
+
+    pub fn memory(&self) -> Option<&Memory> {
+        self.memory.as_ref()
+    }
+
+    /// `eye` and `look` are position and direction of the bot's view, `own_collider` is
+    /// ignored by line of sight checks.
+    #[allow(clippy::too_many_arguments)]
+    pub fn update(
+        &mut self,
+        dt: f32,
+        physics: &Physics,
+        eye: Vector3<f32>,
+        look: Vector3<f32>,
+        own_collider: ColliderHandle,
+        targets: &[Target],
+        noises: &[Noise],
+    ) {
+        if let Some(memory) = self.memory.as_mut() {
+            memory.age += dt;
+            if memory.age > MEMORY_TIME {
+                self.memory = None;
+            }
+        }
+
+        self.visible_target = targets
+            .iter()
+            .filter(|target| self.can_see(physics, eye, look, own_collider, target))
+            .map(|target| target.position)
+            .min_by(|a, b| (a - eye).norm().total_cmp(&(b - eye).norm()));
+        if let Some(position) = self.visible_target {
+            self.remember(position, Sense::Sight);
+            return;
+        }
+
+        // Sight always wins, hearing only tells roughly where to look. Only noises of targets
+        // count, so a bot does not turn to its own shots or shots of its allies.
+        if let Some(noise) = noises
+            .iter()
+            .filter(|noise| targets.iter().any(|t| t.collider == noise.emitter))
+            .filter(|noise| (noise.position - eye).norm() <= noise.radius)
+            .min_by(|a, b| {
+                (a.position - eye)
+                    .norm()
+                    .total_cmp(&(b.position - eye).norm())
+            })
+        {
+            self.remember(noise.position, Sense::Hearing(noise.kind));
+        }
+    }
+
+    fn remember(&mut self, position: Vector3<f32>, sense: Sense) {
+        self.memory = Some(Memory {
+            position,
+            sense,
+            age: 0.0,
+        });
+    }
+
+    fn can_see(
+        &self,
+        physics: &Physics,
+        eye: Vector3<f32>,
+        look: Vector3<f32>,
+        own_collider: ColliderHandle,
+        target: &Target,
+    ) -> bool {
+        let to_target = target.position - eye;
+        let distance = to_target.norm();
+        if distance > self.view_distance {
+            return false;
+        }
+        let cos = to_target
+            .try_normalize(f32::EPSILON)
+            .map_or(1.0, |dir| dir.dot(&look.normalize()));
+        if cos < (self.fov * 0.5).cos() {
+            return false;
+        }
+
+        // Line of sight is checked the same way as bullets fly - the first thing on the way
+        // must be the target itself.
+        let mut intersections = Vec::new();
+        physics.cast_ray(
+            RayCastOptions {
+                ray: Ray::new(eye, to_target),
+                max_len: distance,
+                // Hitboxes are inside of capsules, only capsules block the sight.
+                groups: layers::query(layers::BOT, layers::SOLID),
+                sort_results: true,
+            },
+            &mut intersections,
+        );
+        intersections
+            .iter()
+            .find(|i| i.collider != own_collider)
+            .is_some_and(|i| i.collider == target.collider)
+    }
+
+    // Short human-readable summary for the console.
+    pub fn describe(&self) -> String {
+        let seeing = match self.visible_target {
+            Some(p) => format!("sees enemy at ({:.1}, {:.1}, {:.1})", p.x, p.y, p.z),
+            None => "sees nothing".to_owned(),
+        };
+        let memory = match self.memory.as_ref() {
+            Some(m) => format!(
+                "remembers ({:.1}, {:.1}, {:.1}) by {:?}, {:.1} s ago",
+                m.position.x, m.position.y, m.position.z, m.sense, m.age
+            ),
+            None => "remembers nothing".to_owned(),
+        };
+        format!(
+            "{}, {} (fov {:.0}, view distance {:.0})",
+            seeing,
+            memory,
+            self.fov.to_degrees(),
+            self.view_distance
+        )
+    }
+}
@@ -18,7 +18,19 @@ use rg3d::{
     },
 };
 
-use crate::{message::Message, weapon::Weapon};
+use crate::{hitbox::Hitboxes, layers, message::Message, weapon::Weapon};
+
+pub const MAX_HEALTH: f32 = 100.0;
+pub const MAX_ARMOR: f32 = 100.0;
+
+// Part of the damage absorbed by armor while it lasts.
+const ARMOR_ABSORPTION: f32 = 0.5;
+
+const SPAWN_POSITION: [f32; 3] = [0.0, 1.0, -1.0];
+
+// Capsule sizes of the player.
+const HALF_HEIGHT: f32 = 0.25;
+const RADIUS: f32 = 0.2;
 
 #[derive(Default)]
 pub struct InputController {
@@ -29,6 +41,8 @@ pub struct InputController {
     pitch: f32,
     yaw: f32,
     shoot: bool,
+    // Grenade is cooked while the key is held and thrown when it is let go.
+    throw: bool,
 }
 
 pub struct Player {
@@ -38,8 +52,13 @@ pub struct Player {
     pub weapon: Handle<Weapon>,
     pub rigid_body: RigidBodyHandle,
     pub collider: ColliderHandle,
+    pub hitboxes: Hitboxes,
     pub controller: InputController,
     pub sender: Sender<Message>,
+    pub health: f32,
+    pub armor: f32,
+    pub god: bool,
+    noclip: bool,
 }
 
 async fn create_skybox(resource_manager: ResourceManager) -> SkyBox {
@@ -97,14 +116,17 @@ impl Player {
         let rigid_body_handle = scene.physics.add_body(
             RigidBodyBuilder::new_dynamic()
                 .lock_rotations()
-                .translation(0.0, 1.0, -1.0)
+                .translation(SPAWN_POSITION[0], SPAWN_POSITION[1], SPAWN_POSITION[2])
                 .build(),
         );
         let collider = scene.physics.add_collider(
-            ColliderBuilder::capsule_y(0.25, 0.2).build(),
+            ColliderBuilder::capsule_y(HALF_HEIGHT, RADIUS)
+                .collision_groups(layers::collider(layers::PLAYER))
+                .build(),
             rigid_body_handle,
         );
         scene.physics_binder.bind(pivot, rigid_body_handle);
+        let hitboxes = Hitboxes::attach(scene, pivot, layers::PLAYER);
         Self {
             pivot,
             camera,
@@ -112,26 +134,100 @@ impl Player {
             weapon: Default::default(),
             rigid_body: rigid_body_handle,
             collider,
+            hitboxes,
             controller: Default::default(),
             sender,
+            health: MAX_HEALTH,
+            armor: 0.0,
+            god: false,
+            noclip: false,
         }
     }
+
+    pub fn is_noclip(&self) -> bool {
+        self.noclip
+    }
+
+    // In noclip mode the player flies in the look direction, ignores gravity and passes
+    // through everything.
+    pub fn set_noclip(&mut self, scene: &mut Scene, noclip: bool) {
+        self.noclip = noclip;
+        // Colliders cannot be changed in-place, so replace the capsule with a sensor one.
+        scene.physics.remove_collider(self.collider);
+        self.collider = scene.physics.add_collider(
+            ColliderBuilder::capsule_y(HALF_HEIGHT, RADIUS)
+                .sensor(noclip)
+                .collision_groups(layers::collider(layers::PLAYER))
+                .build(),
+            self.rigid_body,
+        );
+        let body = scene
+            .physics
+            .bodies
+            .get_mut(self.rigid_body.into())
+            .unwrap();
+        body.set_gravity_scale(if noclip { 0.0 } else { 1.0 }, true);
+    }
+
+    // Returns true if the damage was lethal.
+    pub fn damage(&mut self, amount: f32) -> bool {
+        if !self.god {
+            let absorbed = (amount * ARMOR_ABSORPTION).min(self.armor);
+            self.armor -= absorbed;
+            self.health = (self.health - (amount - absorbed)).max(0.0);
+        }
+        self.health <= 0.0
+    }
+
+    // Player makes footstep noise while walking on the ground, flying in noclip is silent.
+    pub fn is_walking(&self, scene: &Scene) -> bool {
+        let velocity = scene
+            .physics
+            .bodies
+            .get(self.rigid_body.into())
+            .unwrap()
+            .linvel();
+        !self.noclip && Vector3::new(velocity.x, 0.0, velocity.z).norm() > 0.1
+    }
+
+    // Releases every held button, used when input goes somewhere else (console, menus).
+    pub fn reset_input(&mut self) {
+        if self.controller.throw {
+            self.sender.send(Message::ThrowGrenade).unwrap();
+        }
+        self.controller = InputController {
+            pitch: self.controller.pitch,
+            yaw: self.controller.yaw,
+            ..Default::default()
+        };
+    }
+
     pub fn update(&mut self, scene: &mut Scene) {
         scene.graph[self.camera].local_transform_mut().set_rotation(
             UnitQuaternion::from_axis_angle(&Vector3::x_axis(), self.controller.pitch.to_radians()),
         );
+        // Noclip flies where the camera looks, otherwise movement is horizontal only.
+        let look_vector = if self.noclip {
+            scene.graph[self.camera].look_vector()
+        } else {
+            scene.graph[self.pivot].look_vector()
+        };
         let pivot = &mut scene.graph[self.pivot];
         let body = scene
             .physics
             .bodies
             .get_mut(self.rigid_body.into())
             .unwrap();
-        let mut velocity = Vector3::new(0.0, body.linvel().y, 0.0);
+        let mut velocity = if self.noclip {
+            Vector3::default()
+        } else {
+            Vector3::new(0.0, body.linvel().y, 0.0)
+        };
         if self.controller.move_forward {
-            velocity += pivot.look_vector();
+            velocity += look_vector;
         }
         if self.controller.move_backward {
-            velocity -= pivot.look_vector();
+            velocity -= look_vector;
         }
         if self.controller.move_left {
             velocity += pivot.side_vector();
@@ -144,6 +240,7 @@ impl Player {
         position.rotation =
             UnitQuaternion::from_axis_angle(&Vector3::y_axis(), self.controller.yaw.to_radians());
         body.set_position(position, true);
+        self.hitboxes.update(scene);
         if self.controller.shoot {
             self.sender
                 .send(Message::ShootWeapon {
@@ -172,6 +269,26 @@ impl Player {
                         VirtualKeyCode::D => {
                             self.controller.move_right = input.state == ElementState::Pressed;
                         }
+                        VirtualKeyCode::G => {
+                            // Held key repeats presses, only the first one matters.
+                            let throw = input.state == ElementState::Pressed;
+                            if throw != self.controller.throw {
+                                let message = if throw {
+                                    Message::CookGrenade
+                                } else {
+                                    Message::ThrowGrenade
+                                };
+                                self.sender.send(message).unwrap();
+                                self.controller.throw = throw;
+                            }
+                        }
+                        VirtualKeyCode::R if input.state == ElementState::Pressed => {
+                            self.sender
+                                .send(Message::ReloadWeapon {
+                                    weapon: self.weapon,
+                                })
+                                .unwrap();
+                        }
                         _ => (),
                     }
                 }
@@ -0,0 +1,277 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, ErrorKind, Read, Write},
+    net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::console::CommandRegistry;
+
+// Default TCP port of remote console.
+pub const RCON_PORT: u16 = 27015;
+
+// Client is disconnected after this amount of wrong passwords.
+const MAX_AUTH_ATTEMPTS: u32 = 3;
+
+// Protects from clients that send endless line without line break.
+const MAX_LINE_LENGTH: usize = 4096;
+
+// Protects from clients that send commands but never read replies.
+const MAX_PENDING_OUTPUT: usize = 1 << 20;
+
+struct Client {
+    stream: TcpStream,
+    address: SocketAddr,
+    buffer: Vec<u8>,
+    // Replies the socket did not accept yet.
+    output: Vec<u8>,
+    authenticated: bool,
+    failed_attempts: u32,
+    // Client sent everything it had or a line too long, it is dropped once the replies are sent.
+    finished: bool,
+    // Client is dropped right away, because of too many wrong passwords, a write failure or
+    // replies it does not read.
+    closed: bool,
+}
+
+impl Client {
+    // Every reply is a set of lines prefixed with `+` on success or `-` on failure, empty
+    // line marks the end of the reply.
+    fn reply(&mut self, result: &Result<String, String>) {
+        let (prefix, text) = match result {
+            Ok(text) => ('+', text),
+            Err(text) => ('-', text),
+        };
+        let mut reply = String::new();
+        for line in text.lines() {
+            reply += &format!("{} {}\n", prefix, line);
+        }
+        if text.is_empty() {
+            reply += &format!("{}\n", prefix);
+        }
+        reply += "\n";
+        self.output.extend_from_slice(reply.as_bytes());
+        self.flush();
+    }
+
+    // Writes as much of pending replies as the socket takes now, the rest waits for the next
+    // poll.
+    fn flush(&mut self) {
+        while !self.output.is_empty() {
+            match self.stream.write(&self.output) {
+                Ok(0) => {
+                    self.closed = true;
+                    return;
+                }
+                Ok(size) => {
+                    self.output.drain(..size);
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(_) => {
+                    self.closed = true;
+                    return;
+                }
+            }
+        }
+        if self.output.len() > MAX_PENDING_OUTPUT {
+            self.closed = true;
+        }
+    }
+
+    // Reads everything that is available now and returns complete lines. Lines which came
+    // before the client finished sending are returned as well.
+    fn read_lines(&mut self) -> Vec<String> {
+        let mut chunk = [0; 512];
+        while !self.finished {
+            match self.stream.read(&mut chunk) {
+                Ok(0) => self.finished = true,
+                Ok(size) => self.buffer.extend_from_slice(&chunk[..size]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(_) => self.finished = true,
+            }
+        }
+        let mut lines = Vec::new();
+        while let Some(end) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line = self.buffer.drain(..=end).collect::<Vec<_>>();
+            lines.push(String::from_utf8_lossy(&line).trim().to_owned());
+        }
+        if self.buffer.len() > MAX_LINE_LENGTH {
+            self.buffer.clear();
+            self.finished = true;
+        }
+        lines
+    }
+}
+
+/// Append-only record of everything remote admins did. Passwords are never written.
+struct AuditLog {
+    file: File,
+}
+
+impl AuditLog {
+    fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(Self {
+            file: OpenOptions::new().create(true).append(true).open(path)?,
+        })
+    }
+
+    fn record(&mut self, address: SocketAddr, status: &str, entry: &str) {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        if let Err(e) = writeln!(self.file, "{} {} {} {}", time, address, status, entry) {
+            println!("Unable to write RCON audit log: {}", e);
+        }
+    }
+}
+
+/// Remote console. Clients connect over TCP, authenticate with `auth <password>` and then
+/// send console commands one per line.
+pub struct Rcon {
+    listener: TcpListener,
+    clients: Vec<Client>,
+    password: String,
+    audit_log: AuditLog,
+}
+
+impl Rcon {
+    pub fn bind<P: AsRef<Path>>(port: u16, password: String, audit_log: P) -> io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::UNSPECIFIED, port))?;
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            listener,
+            clients: Default::default(),
+            password,
+            audit_log: AuditLog::open(audit_log)?,
+        })
+    }
+
+    // Must be called every frame, it never blocks.
+    pub fn poll(&mut self, registry: &mut CommandRegistry) {
+        loop {
+            match self.listener.accept() {
+                Ok((stream, address)) => {
+                    if stream.set_nonblocking(true).is_ok() {
+                        self.audit_log.record(address, "CONNECT", "");
+                        self.clients.push(Client {
+                            stream,
+                            address,
+                            buffer: Default::default(),
+                            output: Default::default(),
+                            authenticated: false,
+                            failed_attempts: 0,
+                            finished: false,
+                            closed: false,
+                        });
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    println!("RCON accept failed: {}", e);
+                    break;
+                }
+            }
+        }
+
+        for client in self.clients.iter_mut() {
+            client.flush();
+            for line in client.read_lines() {
+                if client.closed {
+                    break;
+                }
+                if line.is_empty() {
+                    continue;
+                }
+                if !client.authenticated {
+                    let result = match line.strip_prefix("auth ") {
+                        Some(password) if constant_time_eq(password, &self.password) => {
+                            client.authenticated = true;
+                            self.audit_log.record(client.address, "AUTH", "");
+                            Ok("authenticated".to_owned())
+                        }
+                        Some(_) => {
+                            client.failed_attempts += 1;
+                            self.audit_log.record(client.address, "AUTH-FAIL", "");
+                            if client.failed_attempts >= MAX_AUTH_ATTEMPTS {
+                                client.closed = true;
+                            }
+                            Err("invalid password".to_owned())
+                        }
+                        None => Err("not authenticated, use: auth <password>".to_owned()),
+                    };
+                    client.reply(&result);
+                } else {
+                    let result = registry.execute_remote(&line);
+                    self.audit_log.record(
+                        client.address,
+                        if result.is_ok() { "OK" } else { "ERR" },
+                        &line,
+                    );
+                    client.reply(&result);
+                }
+            }
+        }
+
+        let audit_log = &mut self.audit_log;
+        self.clients.retain(|client| {
+            let gone = client.closed || client.finished && client.output.is_empty();
+            if gone {
+                audit_log.record(client.address, "DISCONNECT", "");
+            }
+            !gone
+        });
+    }
+}
+
+// Compares passwords without early exit, so response time does not leak matching prefix.
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::console::CvarValue;
+    use std::{net::Shutdown, sync::mpsc, time::Duration};
+
+    #[test]
+    fn commands_sent_before_shutdown_are_executed() {
+        let audit_path = std::env::temp_dir().join(format!("rcon_test_{}.log", std::process::id()));
+        let mut rcon = Rcon::bind(0, "secret".to_owned(), &audit_path).unwrap();
+        let (sender, _receiver) = mpsc::channel();
+        let mut registry = CommandRegistry::new(sender);
+        registry
+            .cvars_mut()
+            .register("sv_name", CvarValue::String(String::new()), "");
+
+        let port = rcon.listener.local_addr().unwrap().port();
+        let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, port)).unwrap();
+        stream
+            .write_all(b"auth secret\nsv_name \"Test server\"\n")
+            .unwrap();
+        stream.shutdown(Shutdown::Write).unwrap();
+        for _ in 0..100 {
+            rcon.poll(&mut registry);
+            if registry.cvars().string("sv_name") == "Test server" && rcon.clients.is_empty() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        assert_eq!(registry.cvars().string("sv_name"), "Test server");
+        assert!(rcon.clients.is_empty());
+        let mut replies = String::new();
+        stream.read_to_string(&mut replies).unwrap();
+        assert_eq!(replies, "+ authenticated\n\n+\n\n");
+        let audit = std::fs::read_to_string(&audit_path).unwrap();
+        std::fs::remove_file(&audit_path).unwrap();
+        assert!(audit.contains("OK sv_name \"Test server\""));
+        assert!(audit.contains("DISCONNECT"));
+    }
+}
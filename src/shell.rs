@@ -0,0 +1,144 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, RwLock},
+};
+
+use rg3d::{
+    core::{
+        algebra::{Isometry3, Matrix4, Translation3, UnitQuaternion, Vector3},
+        color::Color,
+        pool::Handle,
+        rand::Rng,
+    },
+    physics::{dynamics::RigidBodyBuilder, geometry::ColliderBuilder},
+    renderer::surface::{SurfaceBuilder, SurfaceSharedData},
+    scene::{base::BaseBuilder, mesh::MeshBuilder, node::Node, Scene},
+};
+
+use crate::layers;
+
+// Casing is a small cylinder lying along Z axis.
+const RADIUS: f32 = 0.005;
+const LENGTH: f32 = 0.022;
+// Velocity of a casing in the weapon's space, it flies out to the side, up and a bit back.
+const EJECTION_VELOCITY: [f32; 3] = [1.8, 1.2, -0.4];
+// Every casing flies a bit differently.
+const VELOCITY_SPREAD: f32 = 0.4;
+const MAX_SPIN: f32 = 20.0;
+
+struct Shell {
+    node: Handle<Node>,
+    time_left: f32,
+}
+
+/// Spent shell casings ejected by weapons. They are physical, but never hit anything but the
+/// world and props. There are never more than the limit of them, the oldest ones are removed
+/// first.
+pub struct Shells {
+    shells: VecDeque<Shell>,
+    // Every casing looks the same.
+    shape: Arc<RwLock<SurfaceSharedData>>,
+}
+
+impl Default for Shells {
+    fn default() -> Self {
+        Self {
+            shells: Default::default(),
+            shape: Arc::new(RwLock::new(SurfaceSharedData::make_cylinder(
+                8,
+                RADIUS,
+                LENGTH,
+                true,
+                // Vertical cylinder is turned to lie along Z axis and centered.
+                UnitQuaternion::from_axis_angle(&Vector3::x_axis(), 90.0f32.to_radians())
+                    .to_homogeneous()
+                    * Matrix4::new_translation(&Vector3::new(0.0, -LENGTH * 0.5, 0.0)),
+            ))),
+        }
+    }
+}
+
+impl Shells {
+    /// Ejects a casing at the point, `orientation` is the weapon's orientation in the world.
+    pub fn eject<R: Rng>(
+        &mut self,
+        scene: &mut Scene,
+        rng: &mut R,
+        position: Vector3<f32>,
+        orientation: UnitQuaternion<f32>,
+        lifetime: f32,
+        limit: usize,
+    ) {
+        while !self.shells.is_empty() && self.shells.len() >= limit {
+            let shell = self.shells.pop_front().unwrap();
+            Self::remove(scene, shell.node);
+        }
+        if limit == 0 || lifetime <= 0.0 {
+            return;
+        }
+
+        let [x, y, z] = EJECTION_VELOCITY;
+        let mut spread = || rng.gen_range(-VELOCITY_SPREAD..VELOCITY_SPREAD);
+        let velocity = orientation * Vector3::new(x + spread(), y + spread(), z + spread());
+        let spin = Vector3::new(
+            rng.gen_range(-MAX_SPIN..MAX_SPIN),
+            rng.gen_range(-MAX_SPIN..MAX_SPIN),
+            rng.gen_range(-MAX_SPIN..MAX_SPIN),
+        );
+        let node = MeshBuilder::new(BaseBuilder::new())
+            .with_surfaces(vec![SurfaceBuilder::new(self.shape.clone())
+                // Brass.
+                .with_color(Color::from_rgba(200, 160, 60, 255))
+                .build()])
+            .with_cast_shadows(false)
+            .build(&mut scene.graph);
+        let body = scene.physics.add_body(
+            RigidBodyBuilder::new_dynamic()
+                .position(Isometry3::from_parts(
+                    Translation3::from(position),
+                    orientation,
+                ))
+                .linvel(velocity.x, velocity.y, velocity.z)
+                .angvel(spin)
+                .build(),
+        );
+        scene.physics.add_collider(
+            ColliderBuilder::cuboid(RADIUS, RADIUS, LENGTH * 0.5)
+                .collision_groups(layers::collider(layers::DEBRIS))
+                .restitution(0.3)
+                .build(),
+            body,
+        );
+        scene.physics_binder.bind(node, body);
+        self.shells.push_back(Shell {
+            node,
+            time_left: lifetime,
+        });
+    }
+
+    pub fn update(&mut self, scene: &mut Scene, dt: f32) {
+        for shell in self.shells.iter_mut() {
+            shell.time_left -= dt;
+        }
+        // Casings are ejected one after another, so the oldest ones are gone first.
+        while let Some(shell) = self.shells.front() {
+            if shell.time_left > 0.0 {
+                break;
+            }
+            Self::remove(scene, shell.node);
+            self.shells.pop_front();
+        }
+    }
+
+    // Casings of the previous level are gone together with it.
+    pub fn clear(&mut self) {
+        self.shells.clear();
+    }
+
+    // Body of a casing is removed together with its node.
+    fn remove(scene: &mut Scene, node: Handle<Node>) {
+        if scene.graph.is_valid_handle(node) {
+            scene.remove_node(node);
+        }
+    }
+}
@@ -0,0 +1,88 @@
+/// Top-level state of the game. It decides what is simulated, what is drawn and where the
+/// input goes, everything else lives in `Game`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GameState {
+    MainMenu,
+    Loading,
+    Playing,
+    Paused,
+    GameOver,
+}
+
+/// Everything that may cause a state change.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StateEvent {
+    // Start a new game or load another map.
+    LoadLevel,
+    LevelLoaded,
+    LoadFailed,
+    // Escape key, pauses or resumes the game.
+    TogglePause,
+    Resume,
+    PlayerDied,
+    ReturnToMenu,
+}
+
+impl GameState {
+    /// Returns the state the game ends up in after given event or `None` if the event means
+    /// nothing in this state. There is no rendering or engine involved, so every transition
+    /// can be checked without a window.
+    pub fn next(self, event: StateEvent) -> Option<GameState> {
+        use GameState::*;
+        use StateEvent::*;
+
+        match (self, event) {
+            (Loading, LoadLevel) => None,
+            (_, LoadLevel) => Some(Loading),
+            (Loading, LevelLoaded) => Some(Playing),
+            (Loading, LoadFailed) => Some(MainMenu),
+            (Playing, TogglePause) => Some(Paused),
+            (Paused, TogglePause) | (Paused, Resume) => Some(Playing),
+            (Playing, PlayerDied) => Some(GameOver),
+            (Paused, ReturnToMenu) | (GameOver, ReturnToMenu) => Some(MainMenu),
+            _ => None,
+        }
+    }
+
+    // Scene is simulated and player controls the character only while playing, every other
+    // state freezes the game.
+    pub fn is_simulating(self) -> bool {
+        self == GameState::Playing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GameState::*, StateEvent::*};
+
+    #[test]
+    fn menu_starts_game() {
+        assert_eq!(MainMenu.next(LoadLevel), Some(Loading));
+        assert_eq!(Loading.next(LevelLoaded), Some(Playing));
+        assert_eq!(Loading.next(LoadFailed), Some(MainMenu));
+        // Level already being loaded is not loaded again.
+        assert_eq!(Loading.next(LoadLevel), None);
+        assert_eq!(MainMenu.next(TogglePause), None);
+    }
+
+    #[test]
+    fn pause_and_resume() {
+        assert_eq!(Playing.next(TogglePause), Some(Paused));
+        assert_eq!(Paused.next(TogglePause), Some(Playing));
+        assert_eq!(Paused.next(Resume), Some(Playing));
+        assert_eq!(Playing.next(Resume), None);
+        assert!(Playing.is_simulating());
+        assert!(!Paused.is_simulating());
+    }
+
+    #[test]
+    fn game_over_restarts_or_returns_to_menu() {
+        assert_eq!(Playing.next(PlayerDied), Some(GameOver));
+        assert_eq!(GameOver.next(LoadLevel), Some(Loading));
+        assert_eq!(GameOver.next(ReturnToMenu), Some(MainMenu));
+        assert_eq!(GameOver.next(TogglePause), None);
+        assert_eq!(Paused.next(ReturnToMenu), Some(MainMenu));
+        assert_eq!(Playing.next(ReturnToMenu), None);
+        assert!(!GameOver.is_simulating());
+    }
+}
@@ -0,0 +1,185 @@
+use std::path::Path;
+
+use rg3d::core::color::Color;
+
+/// How shots of a weapon loaded with some ammo are drawn.
+pub struct Tracer {
+    // Weapon and ammo the tracer is for, `*` matches any.
+    weapon: String,
+    ammo: String,
+    color: Color,
+    // Used instead of the color with the colorblind palette.
+    colorblind_color: Color,
+    pub width: f32,
+    pub lifetime: f32,
+    // Only every Nth round is drawn.
+    pub every: u32,
+    // Speed of the head of the tracer in meters per second, it reaches the end of the trail
+    // at once if it is zero.
+    pub speed: f32,
+    // Length of the trail behind the head, the whole trail from the muzzle if it is zero.
+    pub tail: f32,
+    // Whether the tracer fades out over its lifetime.
+    pub fade: bool,
+    // Only that many pellets of a shot are drawn.
+    pub pellets: usize,
+}
+
+impl Tracer {
+    fn new(weapon: &str, ammo: &str) -> Self {
+        Self {
+            weapon: weapon.to_owned(),
+            ammo: ammo.to_owned(),
+            color: Color::from_rgba(255, 255, 0, 120),
+            // Orange of Okabe-Ito palette, it is seen well by every kind of color blindness.
+            colorblind_color: Color::from_rgba(230, 159, 0, 160),
+            width: 0.0025,
+            lifetime: 0.25,
+            every: 1,
+            speed: 0.0,
+            tail: 0.0,
+            fade: false,
+            pellets: usize::MAX,
+        }
+    }
+
+    pub fn color(&self, colorblind: bool) -> Color {
+        if colorblind {
+            self.colorblind_color
+        } else {
+            self.color
+        }
+    }
+
+    // Whether a round with the number (counting from one) gets a tracer.
+    pub fn is_drawn(&self, round: u32) -> bool {
+        round.is_multiple_of(self.every.max(1))
+    }
+}
+
+/// Every tracer from the tracers file. The most specific one is used for a weapon and its ammo.
+pub struct Tracers {
+    tracers: Vec<Tracer>,
+}
+
+impl Default for Tracers {
+    // Used when tracers file cannot be loaded, every shot is drawn the same.
+    fn default() -> Self {
+        Self {
+            tracers: vec![Tracer::new("*", "*")],
+        }
+    }
+}
+
+impl Tracers {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("unable to read {}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut tracers: Vec<Tracer> = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with("//") || trimmed.starts_with('#') {
+                continue;
+            }
+            let error = |text: &str| Err(format!("line {}: {}", i + 1, text));
+            let tokens = trimmed.split_whitespace().collect::<Vec<_>>();
+            if let ["tracer", weapon, ammo] = tokens.as_slice() {
+                if tracers
+                    .iter()
+                    .any(|t| t.weapon == *weapon && t.ammo == *ammo)
+                {
+                    return error(&format!("tracer for {} {} is defined twice", weapon, ammo));
+                }
+                tracers.push(Tracer::new(weapon, ammo));
+                continue;
+            }
+            let tracer = match tracers.last_mut() {
+                Some(tracer) if line.starts_with(char::is_whitespace) => tracer,
+                _ => return error("property must be indented under a tracer"),
+            };
+            let number = |token: &str| {
+                token
+                    .parse::<f32>()
+                    .map_err(|_| format!("line {}: invalid number '{}'", i + 1, token))
+            };
+            let color = |r: &str, g: &str, b: &str, a: &str| {
+                let channel = |token: &str| {
+                    token
+                        .parse::<u8>()
+                        .map_err(|_| format!("line {}: invalid color '{}'", i + 1, token))
+                };
+                Ok::<_, String>(Color::from_rgba(
+                    channel(r)?,
+                    channel(g)?,
+                    channel(b)?,
+                    channel(a)?,
+                ))
+            };
+            match tokens.as_slice() {
+                ["color", r, g, b, a] => tracer.color = color(r, g, b, a)?,
+                ["colorblind", r, g, b, a] => tracer.colorblind_color = color(r, g, b, a)?,
+                ["width", width] => tracer.width = number(width)?.max(0.0),
+                ["lifetime", time] => match number(time)? {
+                    // Trail is hidden only when its time runs out, so it must have some.
+                    time if time > 0.0 => tracer.lifetime = time,
+                    _ => return error(&format!("invalid lifetime '{}'", time)),
+                },
+                ["every", rounds] => {
+                    tracer.every = match rounds.parse::<u32>() {
+                        Ok(rounds) if rounds > 0 => rounds,
+                        _ => return error(&format!("invalid amount of rounds '{}'", rounds)),
+                    }
+                }
+                ["speed", speed] => tracer.speed = number(speed)?.max(0.0),
+                ["tail", length] => tracer.tail = number(length)?.max(0.0),
+                ["fade"] => tracer.fade = true,
+                ["pellets", pellets] => {
+                    tracer.pellets = match pellets.parse::<usize>() {
+                        Ok(pellets) if pellets > 0 => pellets,
+                        _ => return error(&format!("invalid amount of pellets '{}'", pellets)),
+                    }
+                }
+                _ => return error(&format!("invalid property '{}'", trimmed)),
+            }
+        }
+        if !tracers.iter().any(|t| t.weapon == "*" && t.ammo == "*") {
+            return Err("there is no tracer for any weapon and ammo ('tracer * *')".to_owned());
+        }
+        Ok(Self { tracers })
+    }
+
+    /// Tracer of the weapon and ammo, then of the weapon with any ammo, then of any weapon
+    /// with the ammo, and finally the one for anything.
+    pub fn find(&self, weapon: &str, ammo: &str) -> &Tracer {
+        let keys = [(weapon, ammo), (weapon, "*"), ("*", ammo), ("*", "*")];
+        keys.iter()
+            .find_map(|(weapon, ammo)| {
+                self.tracers
+                    .iter()
+                    .find(|t| t.weapon == *weapon && t.ammo == *ammo)
+            })
+            .unwrap_or(&self.tracers[0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lifetime_must_be_positive() {
+        for lifetime in ["0", "-1"] {
+            let text = format!("tracer * *\n    lifetime {}\n", lifetime);
+            assert_eq!(
+                Tracers::parse(&text).err(),
+                Some(format!("line 2: invalid lifetime '{}'", lifetime))
+            );
+        }
+        assert!(Tracers::parse("tracer * *\n    lifetime 0.1\n").is_ok());
+    }
+}
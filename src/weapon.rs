@@ -1,34 +1,132 @@
 use rg3d::{
-    core::{algebra::Vector3, math::Vector3Ext, pool::Handle},
+    core::{
+        algebra::{UnitQuaternion, Vector3},
+        math::{ray::Ray, Vector3Ext},
+        pool::Handle,
+        rand::{rngs::StdRng, Rng, SeedableRng},
+    },
     engine::resource_manager::ResourceManager,
-    scene::{graph::Graph, node::Node, Scene},
+    scene::{
+        graph::Graph,
+        node::Node,
+        physics::{Intersection, RayCastOptions},
+        Scene,
+    },
 };
 
+use crate::{console::Cvars, layers, material::Materials, weapon_kind::WeaponKind};
+
+// Spread is an angle (in radians) of a cone in which bullets fly, it grows with every shot
+// and recovers over time.
+const MIN_SPREAD: f32 = 0.002;
+const MAX_SPREAD: f32 = 0.05;
+const SPREAD_PER_SHOT: f32 = 0.006;
+const SPREAD_RECOVERY_SPEED: f32 = 0.08;
+
+// Bullets fly no further than that.
+const MAX_SHOT_DISTANCE: f32 = 1000.0;
+
+// Bullet never goes through anything thicker than that, or through more surfaces.
+const MAX_PENETRATION_DEPTH: f32 = 1.0;
+const MAX_PENETRATIONS: usize = 4;
+// Rays going on from a surface start a bit further, so they do not hit the same surface again.
+const EXIT_OFFSET: f32 = 0.001;
+
+// Models may have a node where spent casings fly out, otherwise they fly out that far behind
+// the shot point.
+const EJECTION_POINT: &str = "Weapon:EjectionPoint";
+const EJECTION_OFFSET: f32 = 0.35;
+
+/// Ray of a single pellet of a shot and everything it has hit, closest first.
+pub struct Pellet {
+    pub ray: Ray,
+    pub hits: Vec<Hit>,
+}
+
+impl Pellet {
+    /// Damage and push of the pellet at the hit, before the hit region is taken into account.
+    /// Both are lost going through surfaces and far away from the shot point.
+    pub fn impact(&self, hit: &Hit, kind: &WeaponKind, cvars: &Cvars) -> (f32, f32) {
+        let distance = (hit.intersection.position.coords - self.ray.origin).norm();
+        let damage = kind.damage.unwrap_or_else(|| cvars.float("weapon_damage"))
+            * hit.power
+            * kind.damage_falloff.at(distance);
+        let impulse = cvars.float("g_impact_force") * hit.power * kind.impulse_falloff.at(distance);
+        (damage, impulse)
+    }
+}
+
+/// Collider a pellet has hit.
+pub struct Hit {
+    pub intersection: Intersection,
+    // Part of the pellet's damage left when it hits, it is lost going through surfaces.
+    pub power: f32,
+    // Where the pellet left the collider, if it went through.
+    pub exit: Option<Intersection>,
+}
+
 pub struct Weapon {
+    kind: WeaponKind,
     model: Handle<Node>,
     shot_point: Handle<Node>,
+    ejection_point: Handle<Node>,
     shot_timer: f32,
+    // Rounds fired since the weapon was made.
+    rounds_fired: u32,
     recoil_offset: Vector3<f32>,
     recoil_target_offset: Vector3<f32>,
+    ammo: u32,
+    reserve_ammo: u32,
+    reload_timer: f32,
+    spread: f32,
+    // Layers bullets of the weapon hit.
+    hits: u16,
+    // Layer of whoever holds the weapon, its hitboxes are never hit.
+    owner: u16,
 }
 
 impl Weapon {
-    pub async fn new(scene: &mut Scene, resource_manager: ResourceManager) -> Self {
+    pub async fn new(
+        scene: &mut Scene,
+        resource_manager: ResourceManager,
+        kind: &WeaponKind,
+    ) -> Result<Self, String> {
         let model = resource_manager
-            .request_model("assets/models/m4/m4.FBX")
+            .request_model(&kind.model)
             .await
-            .unwrap()
+            .map_err(|_| format!("unable to load model {} of {}", kind.model, kind.name))?
             .instantiate_geometry(scene);
         let shot_point = scene.graph.find_by_name(model, "Weapon:ShotPoint");
+        let mut weapon = Self::from_model(model, shot_point, kind.clone());
+        weapon.ejection_point = scene.graph.find_by_name(model, EJECTION_POINT);
+        Ok(weapon)
+    }
+
+    // Weapon doesn't care what its model is, it just moves it on recoil and shoots from
+    // the shot point.
+    pub fn from_model(model: Handle<Node>, shot_point: Handle<Node>, kind: WeaponKind) -> Self {
         Self {
+            ammo: kind.magazine,
+            reserve_ammo: kind.reserve,
+            kind,
             model,
             shot_point,
+            ejection_point: Handle::NONE,
             shot_timer: 0.0,
+            rounds_fired: 0,
             recoil_offset: Default::default(),
             recoil_target_offset: Default::default(),
+            reload_timer: 0.0,
+            spread: MIN_SPREAD,
+            hits: layers::SHOOTABLE,
+            owner: layers::PROJECTILE,
         }
     }
 
+    pub fn set_owner(&mut self, layer: u16) {
+        self.owner = layer;
+    }
+
     pub fn model(&self) -> Handle<Node> {
         self.model
     }
@@ -37,8 +135,76 @@ impl Weapon {
         self.shot_point
     }
 
+    /// Position where spent casings fly out and orientation of the weapon in the world.
+    pub fn ejection(&self, graph: &Graph) -> (Vector3<f32>, UnitQuaternion<f32>) {
+        let model = &graph[self.model];
+        let look = model
+            .look_vector()
+            .try_normalize(f32::EPSILON)
+            .unwrap_or_else(Vector3::z);
+        let up = model
+            .up_vector()
+            .try_normalize(f32::EPSILON)
+            .unwrap_or_else(Vector3::y);
+        let position = if self.ejection_point.is_some() {
+            graph[self.ejection_point].global_position()
+        } else {
+            graph[self.shot_point].global_position() - look.scale(EJECTION_OFFSET)
+        };
+        (position, UnitQuaternion::face_towards(&look, &up))
+    }
+
+    pub fn kind(&self) -> &WeaponKind {
+        &self.kind
+    }
+
+    pub fn rounds_fired(&self) -> u32 {
+        self.rounds_fired
+    }
+
+    pub fn ammo(&self) -> u32 {
+        self.ammo
+    }
+
+    pub fn reserve_ammo(&self) -> u32 {
+        self.reserve_ammo
+    }
+
+    pub fn add_ammo(&mut self, amount: u32) {
+        let max = self.kind.max_reserve.unwrap_or(self.kind.reserve);
+        self.reserve_ammo = self.reserve_ammo.saturating_add(amount).min(max);
+    }
+
+    pub fn spread(&self) -> f32 {
+        self.spread
+    }
+
+    pub fn is_reloading(&self) -> bool {
+        self.reload_timer > 0.0
+    }
+
+    // Magazine is not full and there is something to fill it with.
+    pub fn can_reload(&self) -> bool {
+        !self.is_reloading() && self.ammo < self.kind.magazine && self.reserve_ammo > 0
+    }
+
+    pub fn reload(&mut self) {
+        if self.can_reload() {
+            self.reload_timer = self.kind.reload_time;
+        }
+    }
+
     pub fn update(&mut self, dt: f32, graph: &mut Graph) {
-        self.shot_timer = (self.shot_timer - dt).min(0.0);
+        self.shot_timer = (self.shot_timer - dt).max(0.0);
+        self.spread = (self.spread - SPREAD_RECOVERY_SPEED * dt).max(MIN_SPREAD);
+        if self.is_reloading() {
+            self.reload_timer -= dt;
+            if self.reload_timer <= 0.0 {
+                let amount = (self.kind.magazine - self.ammo).min(self.reserve_ammo);
+                self.ammo += amount;
+                self.reserve_ammo -= amount;
+            }
+        }
         self.recoil_offset.follow(&self.recoil_target_offset, 0.5);
         graph[self.model]
             .local_transform_mut()
@@ -53,11 +219,182 @@ impl Weapon {
     }
 
     pub fn can_shoot(&self) -> bool {
-        self.shot_timer <= 0.0
+        self.shot_timer <= 0.0 && self.ammo > 0 && !self.is_reloading()
     }
 
-    pub fn shoot(&mut self) {
-        self.shot_timer = 0.1;
-        self.recoil_target_offset = Vector3::new(0.0, 0.00625, -0.025);
+    /// Casts rays of every pellet of a shot from the shot point along the model's look
+    /// vector, deviated randomly within the current spread. Pellets of a shot fly in a pattern
+    /// around the aimed direction, which is the same for the same seed and round. Returns ray
+    /// of every pellet and every hit of it on the layers the weapon hits.
+    pub fn cast_shot<R: Rng>(
+        &self,
+        scene: &Scene,
+        materials: &Materials,
+        penetration: f32,
+        seed: u64,
+        rng: &mut R,
+    ) -> Vec<Pellet> {
+        let model = &scene.graph[self.model];
+        let (side, up) = (model.side_vector(), model.up_vector());
+        let aim = deviate(model.look_vector(), side, up, self.spread, rng);
+        let origin = scene.graph[self.shot_point].global_position();
+        let mut pattern = StdRng::seed_from_u64(seed.wrapping_add(u64::from(self.rounds_fired)));
+        (0..self.kind.pellets)
+            .map(|_| {
+                let direction = if self.kind.pellets > 1 {
+                    deviate(aim, side, up, self.kind.pellet_spread, &mut pattern)
+                } else {
+                    aim
+                };
+                Pellet {
+                    // Make a ray that starts at the weapon's position in the world and look
+                    // toward "look" vector of the weapon.
+                    ray: Ray::new(origin, direction.normalize().scale(MAX_SHOT_DISTANCE)),
+                    hits: self.trace(scene, materials, penetration, origin, direction),
+                }
+            })
+            .collect()
     }
+
+    // Every hit of a single pellet. Pellet goes through a surface while it has `penetration`
+    // left, every meter of a surface takes the resistance of its material away.
+    fn trace(
+        &self,
+        scene: &Scene,
+        materials: &Materials,
+        penetration: f32,
+        mut origin: Vector3<f32>,
+        direction: Vector3<f32>,
+    ) -> Vec<Hit> {
+        let direction = direction.normalize();
+        let mut hits = Vec::new();
+        let mut power = penetration;
+        while let Some(intersection) =
+            self.cast(scene, Ray::new(origin, direction), MAX_SHOT_DISTANCE)
+        {
+            let exit = self.exit(scene, &intersection, direction);
+            let thickness = exit.as_ref().map_or(f32::INFINITY, |exit| {
+                exit.position
+                    .coords
+                    .metric_distance(&intersection.position.coords)
+            });
+            let resistance = materials
+                .of_collider(scene, intersection.collider)
+                .resistance;
+            let hit_power = if penetration > 0.0 {
+                power / penetration
+            } else {
+                1.0
+            };
+            power -= resistance * thickness;
+            let exit = exit.filter(|_| power > 0.0 && hits.len() + 1 < MAX_PENETRATIONS);
+            let stopped = exit.is_none();
+            if let Some(exit) = exit.as_ref() {
+                origin = exit.position.coords + direction.scale(EXIT_OFFSET);
+            }
+            hits.push(Hit {
+                intersection,
+                power: hit_power,
+                exit,
+            });
+            if stopped {
+                break;
+            }
+        }
+        hits
+    }
+
+    // Closest hit along the ray on the layers the weapon hits.
+    fn cast(&self, scene: &Scene, ray: Ray, max_len: f32) -> Option<Intersection> {
+        let mut intersections = Vec::new();
+        scene.physics.cast_ray(
+            RayCastOptions {
+                ray,
+                max_len,
+                groups: layers::query(self.owner, self.hits),
+                sort_results: true, // We need intersections to be sorted from closest to furthest.
+            },
+            &mut intersections,
+        );
+        intersections.into_iter().next()
+    }
+
+    // Where a bullet that hit a collider leaves it, nothing is returned if the collider is too
+    // thick. Ray going on from inside of a mesh hits its other side, but solid shapes are hit
+    // at once, so the other side of them is found by a ray cast back from behind.
+    fn exit(
+        &self,
+        scene: &Scene,
+        entry: &Intersection,
+        direction: Vector3<f32>,
+    ) -> Option<Intersection> {
+        let inside = entry.position.coords + direction.scale(EXIT_OFFSET);
+        match self.cast_at(scene, entry, Ray::new(inside, direction)) {
+            Some(exit) if exit.toi > 0.0 => Some(exit),
+            Some(_) => {
+                let behind = entry.position.coords + direction.scale(MAX_PENETRATION_DEPTH);
+                self.cast_at(scene, entry, Ray::new(behind, -direction))
+                    // Still inside of the shape, it is too thick.
+                    .filter(|exit| exit.toi > 0.0)
+            }
+            None => None,
+        }
+        // Mesh is hit from inside, the hole on the other side faces along the bullet anyway.
+        .map(|mut exit| {
+            if exit.normal.dot(&direction) < 0.0 {
+                exit.normal = -exit.normal;
+            }
+            exit
+        })
+    }
+
+    // Hit on the same collider as the entry no further than bullets go through.
+    fn cast_at(&self, scene: &Scene, entry: &Intersection, ray: Ray) -> Option<Intersection> {
+        let mut intersections = Vec::new();
+        scene.physics.cast_ray(
+            RayCastOptions {
+                ray,
+                max_len: MAX_PENETRATION_DEPTH,
+                groups: layers::query(self.owner, self.hits),
+                sort_results: false,
+            },
+            &mut intersections,
+        );
+        intersections
+            .into_iter()
+            .find(|intersection| intersection.collider == entry.collider)
+    }
+
+    // Fire interval of the weapon kind and recoil are the same for the player and bots.
+    pub fn shoot(&mut self, cvars: &Cvars) {
+        self.shot_timer = self
+            .kind
+            .fire_interval
+            .unwrap_or_else(|| cvars.float("weapon_fire_interval"));
+        self.recoil_target_offset = Vector3::new(
+            0.0,
+            cvars.float("weapon_recoil_rise"),
+            -cvars.float("weapon_recoil_kick"),
+        );
+        self.ammo -= 1;
+        self.rounds_fired += 1;
+        self.spread = (self.spread + SPREAD_PER_SHOT).min(MAX_SPREAD);
+        if self.ammo == 0 {
+            self.reload();
+        }
+    }
+}
+
+// Direction deviated randomly within the cone of the angle around the look vector, uniformly
+// over the area of the cone's base.
+fn deviate<R: Rng>(
+    look: Vector3<f32>,
+    side: Vector3<f32>,
+    up: Vector3<f32>,
+    angle: f32,
+    rng: &mut R,
+) -> Vector3<f32> {
+    let turn = rng.gen_range(0.0..std::f32::consts::TAU);
+    let deviation = angle.tan() * rng.gen_range(0.0f32..1.0).sqrt();
+    look + side.scale(deviation * turn.cos()) + up.scale(deviation * turn.sin())
 }
@@ -0,0 +1,330 @@
+use std::path::Path;
+
+// Distances at which damage and impulse of weapons are shown by `weapon_falloff` command.
+const READOUT_DISTANCES: [f32; 9] = [0.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0];
+
+// Every pellet is a ray cast, decal and effect, shots with more of them would stall a frame.
+const MAX_PELLETS: u32 = 64;
+
+/// Piecewise linear curve of a scale by distance. The scale is kept before the first point and
+/// after the last one, and it does not change at all without points.
+#[derive(Clone, Default)]
+pub struct Curve {
+    // Distances and scales at them, the distances go up.
+    points: Vec<(f32, f32)>,
+}
+
+impl Curve {
+    pub fn at(&self, distance: f32) -> f32 {
+        evaluate(&self.points, distance)
+    }
+}
+
+/// Scale at the distance on the curve going through the points sorted by distance.
+fn evaluate(points: &[(f32, f32)], distance: f32) -> f32 {
+    let next = points.iter().position(|(d, _)| *d > distance);
+    match next {
+        None => points.last().map_or(1.0, |(_, scale)| *scale),
+        Some(0) => points[0].1,
+        Some(i) => {
+            let (d0, s0) = points[i - 1];
+            let (d1, s1) = points[i];
+            s0 + (s1 - s0) * (distance - d0) / (d1 - d0)
+        }
+    }
+}
+
+/// Everything that makes weapons of a kind different from others, loaded from the weapons file.
+#[derive(Clone)]
+pub struct WeaponKind {
+    pub name: String,
+    pub model: String,
+    // Type of ammo the weapon is loaded with, tracers refer to it.
+    pub ammo: String,
+    pub magazine: u32,
+    // Rounds the weapon is given with besides the loaded magazine.
+    pub reserve: u32,
+    // Most rounds the weapon carries besides the loaded magazine, the reserve if not given.
+    pub max_reserve: Option<u32>,
+    pub reload_time: f32,
+    // Damage of a single pellet and minimal time between two shots, cvars are used if not given.
+    pub damage: Option<f32>,
+    pub fire_interval: Option<f32>,
+    // Every shot fires that many pellets, each one of them flies in its own direction within
+    // the pellet spread angle (in radians) around the aimed direction.
+    pub pellets: u32,
+    pub pellet_spread: f32,
+    // Parts of the damage and the push of a pellet left at a distance from the shot point.
+    pub damage_falloff: Curve,
+    pub impulse_falloff: Curve,
+}
+
+impl WeaponKind {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            model: "assets/models/m4/m4.FBX".to_owned(),
+            ammo: "ball".to_owned(),
+            magazine: 30,
+            reserve: 90,
+            max_reserve: None,
+            reload_time: 2.0,
+            damage: None,
+            fire_interval: None,
+            pellets: 1,
+            pellet_spread: 0.0,
+            damage_falloff: Default::default(),
+            impulse_falloff: Default::default(),
+        }
+    }
+
+    /// Damage and impulse of a pellet at some distances, one line per distance.
+    pub fn describe_falloff(&self, damage: f32, impulse: f32) -> Vec<String> {
+        let damage = self.damage.unwrap_or(damage);
+        READOUT_DISTANCES
+            .iter()
+            .map(|&distance| {
+                let damage_scale = self.damage_falloff.at(distance);
+                let impulse_scale = self.impulse_falloff.at(distance);
+                format!(
+                    "{:>6.0} m: damage {:5.1} ({:3.0}%), impulse {:5.1} ({:3.0}%)",
+                    distance,
+                    damage * damage_scale,
+                    damage_scale * 100.0,
+                    impulse * impulse_scale,
+                    impulse_scale * 100.0
+                )
+            })
+            .collect()
+    }
+}
+
+impl Default for WeaponKind {
+    // Rifle of bots and the player when weapons file cannot be loaded.
+    fn default() -> Self {
+        Self::new("m4")
+    }
+}
+
+/// Every weapon kind from the weapons file, the first one is given to the player and bots.
+pub struct WeaponKinds {
+    kinds: Vec<WeaponKind>,
+}
+
+impl Default for WeaponKinds {
+    fn default() -> Self {
+        Self {
+            kinds: vec![WeaponKind::default()],
+        }
+    }
+}
+
+impl WeaponKinds {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("unable to read {}: {}", path.display(), e))?;
+        let kinds = Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+        // Models are loaded only when a weapon is given, so missing ones are found here.
+        if let Some(kind) = kinds.kinds.iter().find(|k| !Path::new(&k.model).is_file()) {
+            return Err(format!(
+                "{}: model {} of weapon '{}' does not exist",
+                path.display(),
+                kind.model,
+                kind.name
+            ));
+        }
+        Ok(kinds)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut kinds: Vec<WeaponKind> = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with("//") || trimmed.starts_with('#') {
+                continue;
+            }
+            let error = |text: &str| Err(format!("line {}: {}", i + 1, text));
+            let tokens = trimmed.split_whitespace().collect::<Vec<_>>();
+            if let ["weapon", name] = tokens.as_slice() {
+                if kinds.iter().any(|k| k.name == *name) {
+                    return error(&format!("weapon '{}' is defined twice", name));
+                }
+                kinds.push(WeaponKind::new(name));
+                continue;
+            }
+            let kind = match kinds.last_mut() {
+                Some(kind) if line.starts_with(char::is_whitespace) => kind,
+                _ => return error("property must be indented under a weapon"),
+            };
+            let number = |token: &str| {
+                token
+                    .parse::<f32>()
+                    .map_err(|_| format!("line {}: invalid number '{}'", i + 1, token))
+            };
+            let count = |token: &str| {
+                token
+                    .parse::<u32>()
+                    .map_err(|_| format!("line {}: invalid amount '{}'", i + 1, token))
+            };
+            let curve = |tokens: &[&str]| {
+                if tokens.is_empty() || !tokens.len().is_multiple_of(2) {
+                    return Err(format!(
+                        "line {}: curve needs distance and scale pairs",
+                        i + 1
+                    ));
+                }
+                let mut points = Vec::new();
+                for pair in tokens.chunks(2) {
+                    let (distance, scale) = (number(pair[0])?, number(pair[1])?.max(0.0));
+                    if points.last().is_some_and(|(last, _)| distance <= *last) {
+                        return Err(format!("line {}: distances of a curve must go up", i + 1));
+                    }
+                    points.push((distance, scale));
+                }
+                Ok(Curve { points })
+            };
+            match tokens.as_slice() {
+                ["model", path] => kind.model = path.to_string(),
+                ["ammo", ammo] => kind.ammo = ammo.to_string(),
+                ["magazine", rounds] => match count(rounds)? {
+                    0 => return error("magazine must hold at least one round"),
+                    rounds => kind.magazine = rounds,
+                },
+                ["reserve", rounds] => kind.reserve = count(rounds)?,
+                ["max_reserve", rounds] => kind.max_reserve = Some(count(rounds)?),
+                ["reload", time] => kind.reload_time = number(time)?.max(0.0),
+                ["damage", amount] => kind.damage = Some(number(amount)?.max(0.0)),
+                ["interval", time] => kind.fire_interval = Some(number(time)?.max(0.0)),
+                ["pellets", pellets] => match count(pellets)? {
+                    0 => return error("shot must fire at least one pellet"),
+                    pellets if pellets > MAX_PELLETS => {
+                        return error(&format!("shot fires at most {} pellets", MAX_PELLETS))
+                    }
+                    pellets => kind.pellets = pellets,
+                },
+                ["pellet_spread", angle] => {
+                    kind.pellet_spread = number(angle)?.clamp(0.0, 89.0).to_radians()
+                }
+                ["damage_falloff", points @ ..] => kind.damage_falloff = curve(points)?,
+                ["impulse_falloff", points @ ..] => kind.impulse_falloff = curve(points)?,
+                _ => return error(&format!("invalid property '{}'", trimmed)),
+            }
+        }
+        if kinds.is_empty() {
+            return Err("there are no weapons".to_owned());
+        }
+        if let Some(kind) = kinds
+            .iter()
+            .find(|k| k.max_reserve.is_some_and(|max| max < k.reserve))
+        {
+            return Err(format!(
+                "weapon '{}': reserve is over max_reserve",
+                kind.name
+            ));
+        }
+        Ok(Self { kinds })
+    }
+
+    pub fn first(&self) -> &WeaponKind {
+        &self.kinds[0]
+    }
+
+    pub fn find(&self, name: &str) -> Option<&WeaponKind> {
+        self.kinds.iter().find(|k| k.name == name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.kinds.iter().map(|k| k.name.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POINTS: [(f32, f32); 3] = [(10.0, 1.0), (20.0, 0.5), (40.0, 0.25)];
+
+    fn parse_error(properties: &str) -> Option<String> {
+        WeaponKinds::parse(&format!("weapon test\n{}\n", properties)).err()
+    }
+
+    #[test]
+    fn empty_curve_keeps_full_scale() {
+        assert_eq!(evaluate(&[], 0.0), 1.0);
+        assert_eq!(Curve::default().at(500.0), 1.0);
+    }
+
+    #[test]
+    fn scale_is_kept_outside_of_points() {
+        assert_eq!(evaluate(&POINTS, 0.0), 1.0);
+        assert_eq!(evaluate(&POINTS, 40.0), 0.25);
+        assert_eq!(evaluate(&POINTS, 1000.0), 0.25);
+    }
+
+    #[test]
+    fn scale_at_point_is_exact() {
+        assert_eq!(evaluate(&POINTS, 10.0), 1.0);
+        assert_eq!(evaluate(&POINTS, 20.0), 0.5);
+    }
+
+    #[test]
+    fn scale_goes_linearly_between_points() {
+        assert_eq!(evaluate(&POINTS, 15.0), 0.75);
+        assert_eq!(evaluate(&POINTS, 30.0), 0.375);
+    }
+
+    #[test]
+    fn curves_are_parsed() {
+        let kinds = WeaponKinds::parse("weapon test\n    damage_falloff 10 1 20 0.5\n").unwrap();
+        assert_eq!(kinds.first().damage_falloff.at(15.0), 0.75);
+        assert_eq!(kinds.first().impulse_falloff.at(15.0), 1.0);
+    }
+
+    #[test]
+    fn shipped_weapons_are_valid() {
+        assert!(WeaponKinds::load(crate::WEAPONS_PATH).is_ok());
+    }
+
+    #[test]
+    fn missing_model_is_rejected() {
+        let path = std::env::temp_dir().join(format!("weapons_test_{}.txt", std::process::id()));
+        std::fs::write(&path, "weapon test\n    model no/such/model.fbx\n").unwrap();
+        let result = WeaponKinds::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(result
+            .err()
+            .unwrap()
+            .ends_with("model no/such/model.fbx of weapon 'test' does not exist"));
+    }
+
+    #[test]
+    fn invalid_properties_are_rejected() {
+        let curve_pairs = Some("line 2: curve needs distance and scale pairs".to_owned());
+        assert_eq!(parse_error("    damage_falloff 10 1 20"), curve_pairs);
+        assert_eq!(parse_error("    impulse_falloff"), curve_pairs);
+        assert_eq!(
+            parse_error("    damage_falloff 10 1 10 0.5"),
+            Some("line 2: distances of a curve must go up".to_owned())
+        );
+        assert_eq!(
+            parse_error("    impulse_falloff 20 1 10 0.5"),
+            Some("line 2: distances of a curve must go up".to_owned())
+        );
+        assert_eq!(
+            parse_error("    pellets 0"),
+            Some("line 2: shot must fire at least one pellet".to_owned())
+        );
+        assert_eq!(
+            parse_error("    pellets 100000"),
+            Some(format!(
+                "line 2: shot fires at most {} pellets",
+                MAX_PELLETS
+            ))
+        );
+        assert_eq!(
+            parse_error("    reserve 40\n    max_reserve 30"),
+            Some("weapon 'test': reserve is over max_reserve".to_owned())
+        );
+    }
+}